@@ -0,0 +1,18 @@
+use crate::consts::*;
+
+// init.rs 与 test/ 中各个内核测试共用的启动过程：建立内核堆，解析设备树与启动参数，
+// 初始化物理内存；返回物理内存的结束地址
+pub fn early_init(dtb: usize) -> usize {
+    extern "C" {
+        fn end();
+    }
+    crate::memory::init_heap();
+    crate::drivers::parse_device_tree(dtb);
+    crate::cmdline::init(&crate::drivers::board().bootargs);
+    let (_, memory_end) = crate::drivers::board().kernel_memory();
+    crate::memory::init(
+        ((end as usize - KERNEL_BEGIN_VADDR + KERNEL_BEGIN_PADDR) >> 12) + 1,
+        memory_end >> 12,
+    );
+    memory_end
+}
@@ -0,0 +1,59 @@
+use alloc::{string::String, vec::Vec};
+use log::LevelFilter;
+use spin::Once;
+
+// 内核启动参数，来自设备树 /chosen 节点的 bootargs (即 QEMU 的 -append)
+// 例如: init=rust/wait_test sched=rr timeslice=2 loglevel=debug strace=ls,cat
+pub struct Cmdline {
+    pub init: String,
+    pub scheduler: String,
+    pub time_slice: usize,
+    pub log_level: LevelFilter,
+    // 跟踪这些程序的系统调用
+    pub strace: Vec<String>,
+}
+
+impl Default for Cmdline {
+    fn default() -> Self {
+        Cmdline {
+            init: String::from("rust/user_shell"),
+            scheduler: String::from("rr"),
+            time_slice: 1,
+            log_level: LevelFilter::Info,
+            strace: Vec::new(),
+        }
+    }
+}
+
+static CMDLINE: Once<Cmdline> = Once::new();
+
+pub fn cmdline() -> &'static Cmdline {
+    CMDLINE.r#try().expect("kernel command line is not parsed!")
+}
+
+pub fn init(bootargs: &str) {
+    let mut cmdline = Cmdline::default();
+    let mut unknown = String::new();
+    for option in bootargs.split_whitespace() {
+        let mut kv = option.splitn(2, '=');
+        let key = kv.next().unwrap();
+        let value = kv.next().unwrap_or("");
+        match key {
+            "init" => cmdline.init = String::from(value),
+            "sched" => cmdline.scheduler = String::from(value),
+            "timeslice" => cmdline.time_slice = value.parse().unwrap_or(cmdline.time_slice),
+            "loglevel" => cmdline.log_level = value.parse().unwrap_or(cmdline.log_level),
+            "strace" => cmdline.strace = value.split(',').map(String::from).collect(),
+            _ => {
+                unknown.push(' ');
+                unknown.push_str(option);
+            }
+        }
+    }
+    let cmdline = CMDLINE.call_once(|| cmdline);
+    crate::logging::init(cmdline.log_level);
+    if !unknown.is_empty() {
+        warn!("unknown boot options:{}", unknown);
+    }
+    info!("bootargs: {}", bootargs);
+}
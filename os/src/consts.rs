@@ -1,5 +1,3 @@
-pub const PHYSICAL_MEMORY_END: usize = 0x88000000;
-
 pub const KERNEL_BEGIN_PADDR: usize = 0x80200000;
 pub const KERNEL_BEGIN_VADDR: usize = 0xffffffffc0200000;
 
@@ -13,8 +11,19 @@ pub const PHYSICAL_MEMORY_OFFSET: usize = 0xffffffff40000000;
 pub const PAGE_SIZE: usize = 4096;
 
 pub const KERNEL_STACK_SIZE: usize = 0x80000;
-
+// 内核栈区域占据根页表的一项 (1 GiB)，每个栈槽的低半部分不映射，作为保护区域
+// trap/trap.asm 中的溢出检查依赖这里的布局
+pub const KERNEL_STACK_REGION: usize = 0xffffffff80000000;
+pub const KERNEL_STACK_REGION_SIZE: usize = 0x40000000;
+pub const KERNEL_STACK_SLOT_SIZE: usize = KERNEL_STACK_SIZE << 1;
+pub const KERNEL_STACK_SLOTS: usize = KERNEL_STACK_REGION_SIZE / KERNEL_STACK_SLOT_SIZE;
+
+// Sv39 下用户程序只使用低半部分的 256 GiB 地址空间，用户栈位于其顶端
+pub const USER_END: usize = 0x4000000000;
 pub const USER_STACK_SIZE: usize = 0x80000;
-pub const USER_STACK_OFFSET: usize = 0xffffffff00000000;
+pub const USER_STACK_OFFSET: usize = USER_END - USER_STACK_SIZE;
+// mmap 没有指定地址时从这里开始寻找空闲区域
+pub const USER_MMAP_OFFSET: usize = 0x1000000000;
 
-pub const NOFILE: usize = 16;
+// 每个进程最多打开的文件数
+pub const NOFILE: usize = 1024;
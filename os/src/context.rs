@@ -3,6 +3,7 @@ use riscv::register::sstatus;
 use riscv::register::{scause::Scause, sstatus::Sstatus};
 
 #[repr(C)]
+#[derive(Clone)]
 pub struct TrapFrame {
     pub x: [usize; 32],   // General registers
     pub sstatus: Sstatus, // Supervisor Status Register
@@ -46,6 +47,17 @@ impl Context {
     ) -> Self {
         ContextContent::new_user_thread(entry, ustack_top, satp).push_at(kstack_top)
     }
+
+    // 从 tf 返回用户态，用于 fork 出的子线程
+    pub unsafe fn new_fork(tf: TrapFrame, kstack_top: usize, satp: usize) -> Self {
+        ContextContent {
+            ra: __trapret as usize,
+            satp,
+            s: [0; 12],
+            tf,
+        }
+        .push_at(kstack_top)
+    }
 }
 
 #[repr(C)]
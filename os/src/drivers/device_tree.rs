@@ -0,0 +1,176 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{slice, str};
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+
+#[repr(C)]
+struct FdtHeader {
+    magic: u32,
+    totalsize: u32,
+    off_dt_struct: u32,
+    off_dt_strings: u32,
+    off_mem_rsvmap: u32,
+    version: u32,
+    last_comp_version: u32,
+    boot_cpuid_phys: u32,
+    size_dt_strings: u32,
+    size_dt_struct: u32,
+}
+
+pub struct Node {
+    pub name: String,
+    pub props: BTreeMap<String, Vec<u8>>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn prop_raw(&self, name: &str) -> Option<&[u8]> {
+        self.props.get(name).map(|v| v.as_slice())
+    }
+
+    // 字符串列表中的第一项
+    pub fn prop_str(&self, name: &str) -> Option<&str> {
+        self.prop_strs(name).next()
+    }
+
+    pub fn prop_strs<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a str> {
+        self.prop_raw(name)
+            .unwrap_or(&[])
+            .split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| str::from_utf8(s).ok())
+    }
+
+    pub fn prop_u32(&self, name: &str) -> Option<u32> {
+        self.prop_raw(name)
+            .and_then(|v| read_cells(v, 1))
+            .map(|v| v as u32)
+    }
+
+    // 长度为一个或两个 cell 的整数
+    pub fn prop_usize(&self, name: &str) -> Option<usize> {
+        self.prop_raw(name)
+            .and_then(|v| read_cells(v, v.len() / 4))
+            .map(|v| v as usize)
+    }
+
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.prop_strs("compatible").any(|s| s == compatible)
+    }
+
+    // reg 属性，cell 数目由父节点的 #address-cells 与 #size-cells 决定
+    pub fn reg(&self, address_cells: usize, size_cells: usize) -> Vec<(usize, usize)> {
+        let mut regs = Vec::new();
+        if let Some(value) = self.prop_raw("reg") {
+            let step = (address_cells + size_cells) * 4;
+            for entry in value.chunks_exact(step) {
+                let base = read_cells(entry, address_cells).unwrap_or(0) as usize;
+                let size =
+                    read_cells(&entry[address_cells * 4..], size_cells).unwrap_or(0) as usize;
+                regs.push((base, size));
+            }
+        }
+        regs
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+fn read_cells(data: &[u8], cells: usize) -> Option<u64> {
+    if cells == 0 || cells > 2 || data.len() < cells * 4 {
+        return None;
+    }
+    let mut value = 0u64;
+    for i in 0..cells {
+        value = (value << 32) | be32(&data[i * 4..]) as u64;
+    }
+    Some(value)
+}
+
+fn be32(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+fn align4(x: usize) -> usize {
+    (x + 3) & !3
+}
+
+struct Parser<'a> {
+    structure: &'a [u8],
+    strings: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next_u32(&mut self) -> u32 {
+        let value = be32(&self.structure[self.pos..]);
+        self.pos += 4;
+        value
+    }
+
+    fn cstr(data: &[u8], start: usize) -> &str {
+        let len = data[start..].iter().position(|&b| b == 0).unwrap_or(0);
+        str::from_utf8(&data[start..start + len]).unwrap_or("")
+    }
+
+    // 调用前已经读过 FDT_BEGIN_NODE
+    fn node(&mut self) -> Node {
+        let name = String::from(Self::cstr(self.structure, self.pos));
+        self.pos = align4(self.pos + name.len() + 1);
+        let mut node = Node {
+            name,
+            props: BTreeMap::new(),
+            children: Vec::new(),
+        };
+        loop {
+            match self.next_u32() {
+                FDT_PROP => {
+                    let len = self.next_u32() as usize;
+                    let name_offset = self.next_u32() as usize;
+                    let value = self.structure[self.pos..self.pos + len].to_vec();
+                    self.pos = align4(self.pos + len);
+                    node.props
+                        .insert(String::from(Self::cstr(self.strings, name_offset)), value);
+                }
+                FDT_BEGIN_NODE => node.children.push(self.node()),
+                FDT_NOP => {}
+                FDT_END_NODE => break,
+                // 遇到了 FDT_END 或者数据已损坏
+                _ => break,
+            }
+        }
+        node
+    }
+}
+
+// 解析位于虚拟地址 dtb 处的设备树，结果全部拷贝到堆上，之后原数据所在的内存可以被回收
+pub fn parse(dtb: usize) -> Option<Node> {
+    let header = unsafe { &*(dtb as *const FdtHeader) };
+    if u32::from_be(header.magic) != FDT_MAGIC {
+        return None;
+    }
+    let size = u32::from_be(header.totalsize) as usize;
+    let data = unsafe { slice::from_raw_parts(dtb as *const u8, size) };
+    let struct_begin = u32::from_be(header.off_dt_struct) as usize;
+    let struct_end = struct_begin + u32::from_be(header.size_dt_struct) as usize;
+    let strings_begin = u32::from_be(header.off_dt_strings) as usize;
+    let strings_end = strings_begin + u32::from_be(header.size_dt_strings) as usize;
+    let mut parser = Parser {
+        structure: &data[struct_begin..struct_end],
+        strings: &data[strings_begin..strings_end],
+        pos: 0,
+    };
+    loop {
+        match parser.next_u32() {
+            FDT_NOP => {}
+            FDT_BEGIN_NODE => return Some(parser.node()),
+            _ => return None,
+        }
+    }
+}
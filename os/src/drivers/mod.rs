@@ -0,0 +1,117 @@
+pub mod device_tree;
+pub mod plic;
+pub mod uart;
+pub mod virtio_blk;
+
+use crate::consts::*;
+use crate::memory::access_pa_via_va;
+use alloc::{string::String, vec::Vec};
+use device_tree::Node;
+use spin::Once;
+
+#[derive(Clone, Debug)]
+pub struct MmioDevice {
+    pub base: usize,
+    pub size: usize,
+    pub irq: Option<u32>,
+}
+
+// 从设备树中得到的板级信息
+#[derive(Default)]
+pub struct Board {
+    pub memory: Vec<(usize, usize)>,
+    pub bootargs: String,
+    pub uart: Option<MmioDevice>,
+    pub plic: Option<MmioDevice>,
+    pub virtio: Vec<MmioDevice>,
+    pub initrd: Option<(usize, usize)>,
+}
+
+impl Board {
+    // 内核所在的那一段物理内存 [start, end)
+    pub fn kernel_memory(&self) -> (usize, usize) {
+        let (start, end) = self
+            .memory
+            .iter()
+            .cloned()
+            .find(|&(start, end)| start <= KERNEL_BEGIN_PADDR && KERNEL_BEGIN_PADDR < end)
+            .expect("kernel is not in any memory region!");
+        (start, end.min(start + MAX_PHYSICAL_MEMORY))
+    }
+
+    fn walk(&mut self, node: &Node, address_cells: usize, size_cells: usize) {
+        let device = || {
+            node.reg(address_cells, size_cells)
+                .first()
+                .map(|&(base, size)| MmioDevice {
+                    base,
+                    size,
+                    irq: node.prop_u32("interrupts"),
+                })
+        };
+        if node.prop_str("device_type") == Some("memory") {
+            for (base, size) in node.reg(address_cells, size_cells) {
+                self.memory.push((base, base + size));
+            }
+        } else if node.is_compatible("ns16550a") {
+            self.uart = device();
+        } else if node.is_compatible("riscv,plic0") || node.is_compatible("sifive,plic-1.0.0") {
+            self.plic = device();
+        } else if node.is_compatible("virtio,mmio") {
+            if let Some(device) = device() {
+                self.virtio.push(device);
+            }
+        }
+        let address_cells = node.prop_u32("#address-cells").unwrap_or(2) as usize;
+        let size_cells = node.prop_u32("#size-cells").unwrap_or(1) as usize;
+        for child in node.children.iter() {
+            self.walk(child, address_cells, size_cells);
+        }
+    }
+}
+
+static BOARD: Once<Board> = Once::new();
+
+pub fn board() -> &'static Board {
+    BOARD.r#try().expect("device tree is not parsed!")
+}
+
+// dtb 为 OpenSBI 传入的设备树物理地址，需要在分配物理页帧之前解析
+pub fn parse_device_tree(dtb: usize) {
+    let root = device_tree::parse(access_pa_via_va(dtb)).expect("invalid device tree blob!");
+    let mut board = Board::default();
+    board.walk(&root, 2, 1);
+    if let Some(chosen) = root.child("chosen") {
+        board.bootargs = String::from(chosen.prop_str("bootargs").unwrap_or(""));
+        // QEMU -initrd 提供的镜像
+        if let (Some(start), Some(end)) = (
+            chosen.prop_usize("linux,initrd-start"),
+            chosen.prop_usize("linux,initrd-end"),
+        ) {
+            board.initrd = Some((start, end));
+        }
+    }
+    BOARD.call_once(|| board);
+}
+
+pub fn init() {
+    let board = board();
+    for &(start, end) in board.memory.iter() {
+        info!("memory: [{:#x}, {:#x})", start, end);
+    }
+    if let Some(uart) = board.uart.as_ref() {
+        info!("uart: {:#x}, irq = {:?}", uart.base, uart.irq);
+    }
+    uart::init();
+    if let Some(plic) = board.plic.as_ref() {
+        info!("plic: {:#x}", plic.base);
+    }
+    if let Some((start, end)) = board.initrd {
+        info!("initrd: [{:#x}, {:#x})", start, end);
+    }
+    for virtio in board.virtio.iter() {
+        info!("virtio-mmio: {:#x}, irq = {:?}", virtio.base, virtio.irq);
+    }
+    virtio_blk::init(&board.virtio);
+    println!("++++ setup drivers!   ++++");
+}
@@ -0,0 +1,106 @@
+use super::board;
+use crate::memory::map_mmio;
+use alloc::{collections::BTreeMap, sync::Arc};
+use lazy_static::*;
+use spin::{Mutex, Once};
+
+// hart 0 的 S 态对应 PLIC 的 context 1
+const CONTEXT: usize = 1;
+const MAX_SOURCES: usize = 1024;
+
+pub type IrqHandler = Arc<dyn Fn() + Send + Sync>;
+
+struct Plic {
+    priority: usize,
+    enable: usize,
+    context: usize,
+}
+
+impl Plic {
+    unsafe fn set_priority(&self, irq: u32, priority: u32) {
+        ((self.priority + irq as usize * 4) as *mut u32).write_volatile(priority);
+    }
+
+    unsafe fn set_enable(&self, irq: u32, enable: bool) {
+        let word = (self.enable + irq as usize / 32 * 4) as *mut u32;
+        let bit = 1 << (irq % 32);
+        if enable {
+            word.write_volatile(word.read_volatile() | bit);
+        } else {
+            word.write_volatile(word.read_volatile() & !bit);
+        }
+    }
+
+    unsafe fn set_threshold(&self, threshold: u32) {
+        (self.context as *mut u32).write_volatile(threshold);
+    }
+
+    unsafe fn claim(&self) -> u32 {
+        ((self.context + 4) as *const u32).read_volatile()
+    }
+
+    unsafe fn complete(&self, irq: u32) {
+        ((self.context + 4) as *mut u32).write_volatile(irq);
+    }
+}
+
+static PLIC: Once<Plic> = Once::new();
+
+lazy_static! {
+    static ref HANDLERS: Mutex<BTreeMap<u32, IrqHandler>> = Mutex::new(BTreeMap::new());
+}
+
+fn plic() -> &'static Plic {
+    PLIC.r#try().expect("PLIC is not initialized!")
+}
+
+pub fn init() {
+    let base = board().plic.as_ref().expect("no PLIC in device tree!").base;
+    let enable = base + 0x2000 + 0x80 * CONTEXT;
+    let context = base + 0x20_0000 + 0x1000 * CONTEXT;
+    let plic = PLIC.call_once(|| Plic {
+        priority: map_mmio(base, base + MAX_SOURCES * 4),
+        enable: map_mmio(enable, enable + MAX_SOURCES / 8),
+        context: map_mmio(context, context + 8),
+    });
+    unsafe {
+        for irq in 1..MAX_SOURCES as u32 {
+            plic.set_enable(irq, false);
+        }
+        plic.set_threshold(0);
+    }
+}
+
+// 为中断源 irq 注册处理函数，同时在 PLIC 中打开该中断源
+pub fn register_handler(irq: u32, handler: IrqHandler) {
+    HANDLERS.lock().insert(irq, handler);
+    unsafe {
+        plic().set_priority(irq, 1);
+        plic().set_enable(irq, true);
+    }
+}
+
+pub fn unregister_handler(irq: u32) {
+    unsafe {
+        plic().set_enable(irq, false);
+    }
+    HANDLERS.lock().remove(&irq);
+}
+
+// 处理所有待处理的外部中断：claim，调用对应的处理函数，再 complete
+pub fn handle_interrupt() {
+    loop {
+        let irq = unsafe { plic().claim() };
+        if irq == 0 {
+            break;
+        }
+        let handler = HANDLERS.lock().get(&irq).cloned();
+        match handler {
+            Some(handler) => handler(),
+            None => warn!("unhandled external interrupt {}", irq),
+        }
+        unsafe {
+            plic().complete(irq);
+        }
+    }
+}
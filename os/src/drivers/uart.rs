@@ -0,0 +1,187 @@
+use super::{board, plic};
+use crate::fs::tty::TTY;
+use crate::interrupt::{disable_and_store, restore};
+use crate::memory::map_mmio;
+use crate::sbi;
+use crate::sync::condvar::Condvar;
+use alloc::{collections::VecDeque, sync::Arc};
+use core::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::*;
+use riscv::register::sstatus;
+use spin::{Mutex, Once};
+
+// 16550 寄存器偏移
+const RBR: usize = 0; // 接收缓冲 (读)
+const THR: usize = 0; // 发送保持 (写)
+const IER: usize = 1;
+const FCR: usize = 2;
+const MCR: usize = 4;
+const LSR: usize = 5;
+
+const IER_RX_AVAILABLE: u8 = 1 << 0;
+const IER_TX_EMPTY: u8 = 1 << 1;
+const LSR_DATA_READY: u8 = 1 << 0;
+const LSR_TX_EMPTY: u8 = 1 << 5;
+
+const FIFO_SIZE: usize = 16;
+const TX_BUFFER_SIZE: usize = 4096;
+
+struct Uart {
+    base: usize,
+}
+
+impl Uart {
+    unsafe fn read(&self, reg: usize) -> u8 {
+        ((self.base + reg) as *const u8).read_volatile()
+    }
+
+    unsafe fn write(&self, reg: usize, value: u8) {
+        ((self.base + reg) as *mut u8).write_volatile(value)
+    }
+
+    fn getchar(&self) -> Option<u8> {
+        unsafe {
+            if self.read(LSR) & LSR_DATA_READY != 0 {
+                Some(self.read(RBR))
+            } else {
+                None
+            }
+        }
+    }
+
+    fn tx_empty(&self) -> bool {
+        unsafe { self.read(LSR) & LSR_TX_EMPTY != 0 }
+    }
+
+    // 轮询发送，不经过发送缓冲区
+    fn putchar_sync(&self, ch: u8) {
+        while !self.tx_empty() {}
+        unsafe { self.write(THR, ch) }
+    }
+
+    fn set_tx_interrupt(&self, enable: bool) {
+        let ier = if enable {
+            IER_RX_AVAILABLE | IER_TX_EMPTY
+        } else {
+            IER_RX_AVAILABLE
+        };
+        unsafe { self.write(IER, ier) }
+    }
+
+    // 发送 FIFO 为空时最多可以一次写入 FIFO_SIZE 个字节
+    fn flush(&self, tx: &mut VecDeque<u8>) {
+        if !self.tx_empty() {
+            return;
+        }
+        for _ in 0..FIFO_SIZE {
+            match tx.pop_front() {
+                Some(ch) => unsafe { self.write(THR, ch) },
+                None => break,
+            }
+        }
+    }
+}
+
+static UART: Once<Uart> = Once::new();
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    // 发送缓冲区，由发送中断逐步写入硬件
+    static ref TX: Mutex<VecDeque<u8>> = Mutex::new(VecDeque::with_capacity(TX_BUFFER_SIZE));
+    static ref TX_SPACE: Condvar = Condvar::new();
+}
+
+pub fn init() {
+    let info = match board().uart.as_ref() {
+        Some(info) => info,
+        None => return,
+    };
+    let uart = UART.call_once(|| Uart {
+        base: map_mmio(info.base, info.base + info.size),
+    });
+    unsafe {
+        // 打开并清空 FIFO，OUT2 置位后中断才会被送到 PLIC
+        uart.write(FCR, 0x07);
+        uart.write(MCR, 0x0B);
+        uart.set_tx_interrupt(false);
+    }
+    if let Some(irq) = info.irq {
+        plic::register_handler(irq, Arc::new(handle_interrupt));
+    }
+}
+
+fn handle_interrupt() {
+    let uart = UART.r#try().unwrap();
+    while let Some(ch) = uart.getchar() {
+        TTY.receive(ch);
+    }
+    let mut tx = TX.lock();
+    uart.flush(&mut tx);
+    if tx.is_empty() {
+        uart.set_tx_interrupt(false);
+    }
+    if tx.len() < TX_BUFFER_SIZE {
+        TX_SPACE.notify();
+    }
+}
+
+// 尝试放入发送缓冲区，缓冲区已满时返回 false
+fn try_push(uart: &Uart, ch: u8) -> bool {
+    let flags = disable_and_store();
+    let mut tx = TX.lock();
+    let pushed = tx.len() < TX_BUFFER_SIZE;
+    if pushed {
+        tx.push_back(ch);
+        uart.flush(&mut tx);
+        uart.set_tx_interrupt(!tx.is_empty());
+    }
+    drop(tx);
+    restore(flags);
+    pushed
+}
+
+// panic 之后的输出都轮询发送；panic 可能发生在持有发送缓冲区的锁时
+pub fn enter_panic() {
+    PANICKING.store(true, Ordering::SeqCst);
+    unsafe { TX.force_unlock() }
+}
+
+// 内核输出使用，可能在中断处理中被调用，因此缓冲区满时只能轮询等待
+// 关中断时发送中断不会到来，panic 后也不会再有，这时先发出缓冲区中的字节，再直接发送
+pub fn putchar(ch: u8) {
+    let uart = match UART.r#try() {
+        Some(uart) => uart,
+        None => return sbi::console_putchar(ch as usize),
+    };
+    if PANICKING.load(Ordering::SeqCst) || !sstatus::read().sie() {
+        if let Some(mut tx) = TX.try_lock() {
+            while let Some(ch) = tx.pop_front() {
+                uart.putchar_sync(ch);
+            }
+        }
+        return uart.putchar_sync(ch);
+    }
+    while !try_push(uart, ch) {
+        let flags = disable_and_store();
+        uart.flush(&mut TX.lock());
+        restore(flags);
+    }
+}
+
+// 用户程序输出使用，缓冲区满时让出 CPU，等待发送中断腾出空间
+pub fn write(buf: &[u8]) {
+    let uart = match UART.r#try() {
+        Some(uart) => uart,
+        None => return buf.iter().for_each(|&ch| sbi::console_putchar(ch as usize)),
+    };
+    for &ch in buf {
+        loop {
+            let flags = disable_and_store();
+            if try_push(uart, ch) {
+                restore(flags);
+                break;
+            }
+            TX_SPACE.wait_restore(flags);
+        }
+    }
+}
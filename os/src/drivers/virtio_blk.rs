@@ -0,0 +1,323 @@
+use super::MmioDevice;
+use crate::consts::*;
+use crate::memory::map_mmio;
+use alloc::sync::Arc;
+use core::sync::atomic::{fence, Ordering};
+use rcore_fs::dev::{DevError, Device, Result};
+use spin::{Mutex, Once};
+
+// legacy virtio-mmio 寄存器偏移
+const MAGIC_VALUE: usize = 0x000;
+const VERSION: usize = 0x004;
+const DEVICE_ID: usize = 0x008;
+const HOST_FEATURES: usize = 0x010;
+const GUEST_FEATURES: usize = 0x020;
+const GUEST_PAGE_SIZE: usize = 0x028;
+const QUEUE_SEL: usize = 0x030;
+const QUEUE_NUM_MAX: usize = 0x034;
+const QUEUE_NUM: usize = 0x038;
+const QUEUE_ALIGN: usize = 0x03c;
+const QUEUE_PFN: usize = 0x040;
+const QUEUE_NOTIFY: usize = 0x050;
+const INTERRUPT_STATUS: usize = 0x060;
+const INTERRUPT_ACK: usize = 0x064;
+const STATUS: usize = 0x070;
+const CONFIG: usize = 0x100;
+
+const VIRTIO_MAGIC: u32 = 0x7472_6976;
+const VIRTIO_DEVICE_BLOCK: u32 = 2;
+
+const STATUS_ACKNOWLEDGE: u32 = 1;
+const STATUS_DRIVER: u32 = 2;
+const STATUS_DRIVER_OK: u32 = 4;
+
+const DESC_F_NEXT: u16 = 1;
+const DESC_F_WRITE: u16 = 2;
+
+const BLK_T_IN: u32 = 0;
+const BLK_T_OUT: u32 = 1;
+const BLK_T_FLUSH: u32 = 4;
+
+const BLK_F_FLUSH: u32 = 1 << 9;
+
+const QUEUE_SIZE: usize = 8;
+const SECTOR_SIZE: usize = 512;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Desc {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+#[repr(C)]
+struct AvailRing {
+    flags: u16,
+    idx: u16,
+    ring: [u16; QUEUE_SIZE],
+    used_event: u16,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct UsedElem {
+    id: u32,
+    len: u32,
+}
+
+// legacy 布局要求 used ring 从新的一页开始
+#[repr(C, align(4096))]
+struct UsedRing {
+    flags: u16,
+    idx: u16,
+    ring: [UsedElem; QUEUE_SIZE],
+    avail_event: u16,
+}
+
+#[repr(C, align(4096))]
+struct VirtQueue {
+    desc: [Desc; QUEUE_SIZE],
+    avail: AvailRing,
+    used: UsedRing,
+}
+
+#[repr(C)]
+struct BlkRequest {
+    type_: u32,
+    reserved: u32,
+    sector: u64,
+    data: [u8; SECTOR_SIZE],
+    status: u8,
+}
+
+// 队列与请求缓冲区放在内核的 .bss 中，物理地址可以直接由虚拟地址算出
+static mut QUEUE: VirtQueue = VirtQueue {
+    desc: [Desc {
+        addr: 0,
+        len: 0,
+        flags: 0,
+        next: 0,
+    }; QUEUE_SIZE],
+    avail: AvailRing {
+        flags: 0,
+        idx: 0,
+        ring: [0; QUEUE_SIZE],
+        used_event: 0,
+    },
+    used: UsedRing {
+        flags: 0,
+        idx: 0,
+        ring: [UsedElem { id: 0, len: 0 }; QUEUE_SIZE],
+        avail_event: 0,
+    },
+};
+
+static mut REQUEST: BlkRequest = BlkRequest {
+    type_: 0,
+    reserved: 0,
+    sector: 0,
+    data: [0; SECTOR_SIZE],
+    status: 0,
+};
+
+fn kernel_pa<T>(ptr: *const T) -> u64 {
+    (ptr as usize - PHYSICAL_MEMORY_OFFSET) as u64
+}
+
+struct Inner {
+    base: usize,
+    queue: &'static mut VirtQueue,
+    request: &'static mut BlkRequest,
+    last_used: u16,
+    // 设备支持 flush 命令，即写入可能停留在设备的缓存中
+    flush: bool,
+}
+
+impl Inner {
+    unsafe fn read(&self, reg: usize) -> u32 {
+        ((self.base + reg) as *const u32).read_volatile()
+    }
+
+    unsafe fn write(&self, reg: usize, value: u32) {
+        ((self.base + reg) as *mut u32).write_volatile(value)
+    }
+
+    // 同步地完成一次单扇区读写，数据经过 request.data 中转
+    fn transfer(&mut self, sector: usize, write: bool) -> Result<()> {
+        self.submit(if write { BLK_T_OUT } else { BLK_T_IN }, sector)
+    }
+
+    // flush 请求没有数据部分，描述符链只有请求头与状态
+    fn submit(&mut self, type_: u32, sector: usize) -> Result<()> {
+        let request = &mut *self.request;
+        request.type_ = type_;
+        request.reserved = 0;
+        request.sector = sector as u64;
+        request.status = 0xff;
+        let status = Desc {
+            addr: kernel_pa(&request.status as *const u8),
+            len: 1,
+            flags: DESC_F_WRITE,
+            next: 0,
+        };
+        self.queue.desc[0] = Desc {
+            addr: kernel_pa(request as *const BlkRequest),
+            len: 16,
+            flags: DESC_F_NEXT,
+            next: 1,
+        };
+        if type_ == BLK_T_FLUSH {
+            self.queue.desc[1] = status;
+        } else {
+            let data_flags = if type_ == BLK_T_OUT { 0 } else { DESC_F_WRITE };
+            self.queue.desc[1] = Desc {
+                addr: kernel_pa(request.data.as_ptr()),
+                len: SECTOR_SIZE as u32,
+                flags: data_flags | DESC_F_NEXT,
+                next: 2,
+            };
+            self.queue.desc[2] = status;
+        }
+        let avail = &mut self.queue.avail;
+        avail.ring[avail.idx as usize % QUEUE_SIZE] = 0;
+        fence(Ordering::SeqCst);
+        avail.idx = avail.idx.wrapping_add(1);
+        fence(Ordering::SeqCst);
+        unsafe {
+            self.write(QUEUE_NOTIFY, 0);
+            while (&self.queue.used.idx as *const u16).read_volatile() == self.last_used {}
+            fence(Ordering::SeqCst);
+            self.last_used = self.last_used.wrapping_add(1);
+            let status = self.read(INTERRUPT_STATUS);
+            self.write(INTERRUPT_ACK, status);
+            if (&self.request.status as *const u8).read_volatile() == 0 {
+                Ok(())
+            } else {
+                Err(DevError)
+            }
+        }
+    }
+}
+
+pub struct VirtioBlk {
+    inner: Mutex<Inner>,
+    capacity: usize,
+}
+
+impl Device for VirtioBlk {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let mut inner = self.inner.lock();
+        let len = buf.len().min(self.capacity.saturating_sub(offset));
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done;
+            let begin = pos % SECTOR_SIZE;
+            let count = (SECTOR_SIZE - begin).min(len - done);
+            inner.transfer(pos / SECTOR_SIZE, false)?;
+            buf[done..done + count].copy_from_slice(&inner.request.data[begin..begin + count]);
+            done += count;
+        }
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        let mut inner = self.inner.lock();
+        let len = buf.len().min(self.capacity.saturating_sub(offset));
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done;
+            let begin = pos % SECTOR_SIZE;
+            let count = (SECTOR_SIZE - begin).min(len - done);
+            // 不足一个扇区时先读出原有内容
+            if count < SECTOR_SIZE {
+                inner.transfer(pos / SECTOR_SIZE, false)?;
+            }
+            inner.request.data[begin..begin + count].copy_from_slice(&buf[done..done + count]);
+            inner.transfer(pos / SECTOR_SIZE, true)?;
+            done += count;
+        }
+        Ok(len)
+    }
+
+    // 没有协商 VIRTIO_BLK_F_FLUSH 时设备按写穿处理，写入完成即已落盘
+    fn sync(&self) -> Result<()> {
+        let mut inner = self.inner.lock();
+        if inner.flush {
+            inner.submit(BLK_T_FLUSH, 0)?;
+        }
+        Ok(())
+    }
+}
+
+static BLOCK_DEVICE: Once<Arc<VirtioBlk>> = Once::new();
+
+pub fn block_device() -> Option<Arc<VirtioBlk>> {
+    BLOCK_DEVICE.r#try().cloned()
+}
+
+unsafe fn probe(device: &MmioDevice) -> Option<VirtioBlk> {
+    let base = map_mmio(device.base, device.base + device.size);
+    let mut inner = Inner {
+        base,
+        queue: &mut QUEUE,
+        request: &mut REQUEST,
+        last_used: 0,
+        flush: false,
+    };
+    if inner.read(MAGIC_VALUE) != VIRTIO_MAGIC || inner.read(DEVICE_ID) != VIRTIO_DEVICE_BLOCK {
+        return None;
+    }
+    if inner.read(VERSION) != 1 {
+        warn!(
+            "virtio-mmio {:#x}: only legacy devices are supported",
+            device.base
+        );
+        return None;
+    }
+    inner.write(STATUS, 0);
+    inner.write(STATUS, STATUS_ACKNOWLEDGE);
+    inner.write(STATUS, STATUS_ACKNOWLEDGE | STATUS_DRIVER);
+    // 只协商 flush，其余特性都不使用
+    let features = inner.read(HOST_FEATURES) & BLK_F_FLUSH;
+    inner.write(GUEST_FEATURES, features);
+    inner.flush = features != 0;
+    inner.write(GUEST_PAGE_SIZE, PAGE_SIZE as u32);
+    inner.write(QUEUE_SEL, 0);
+    if (inner.read(QUEUE_NUM_MAX) as usize) < QUEUE_SIZE {
+        warn!("virtio-mmio {:#x}: queue is too small", device.base);
+        return None;
+    }
+    inner.write(QUEUE_NUM, QUEUE_SIZE as u32);
+    inner.write(QUEUE_ALIGN, PAGE_SIZE as u32);
+    inner.write(
+        QUEUE_PFN,
+        (kernel_pa(&*inner.queue as *const VirtQueue) as usize / PAGE_SIZE) as u32,
+    );
+    inner.last_used = inner.queue.used.idx;
+    inner.write(
+        STATUS,
+        STATUS_ACKNOWLEDGE | STATUS_DRIVER | STATUS_DRIVER_OK,
+    );
+    let sectors = inner.read(CONFIG) as usize | (inner.read(CONFIG + 4) as usize) << 32;
+    Some(VirtioBlk {
+        inner: Mutex::new(inner),
+        capacity: sectors * SECTOR_SIZE,
+    })
+}
+
+// 使用找到的第一个块设备，队列是静态分配的，因此只支持一个
+pub fn init(devices: &[MmioDevice]) {
+    for device in devices {
+        if let Some(blk) = unsafe { probe(device) } {
+            info!(
+                "virtio-blk: {:#x}, {} sectors",
+                device.base,
+                blk.capacity / SECTOR_SIZE
+            );
+            BLOCK_DEVICE.call_once(|| Arc::new(blk));
+            return;
+        }
+    }
+}
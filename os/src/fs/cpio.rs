@@ -0,0 +1,97 @@
+use alloc::sync::Arc;
+use core::str;
+use rcore_fs::vfs::*;
+
+// newc 格式: 110 字节的 ASCII 头部，之后是文件名和文件内容，均按 4 字节对齐
+const MAGIC: &[u8] = b"070701";
+const HEADER_SIZE: usize = 110;
+const TRAILER: &str = "TRAILER!!!";
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+pub fn is_cpio(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn align4(x: usize) -> usize {
+    (x + 3) & !3
+}
+
+// 头部中第 index 个 8 位十六进制字段
+fn field(header: &[u8], index: usize) -> Result<usize> {
+    let start = MAGIC.len() + index * 8;
+    str::from_utf8(&header[start..start + 8])
+        .ok()
+        .and_then(|s| usize::from_str_radix(s, 16).ok())
+        .ok_or(FsError::InvalidParam)
+}
+
+// 找到或创建 path 的父目录，返回父目录与最后一级文件名
+fn parent_of<'a>(root: &Arc<dyn INode>, path: &'a str) -> Result<(Arc<dyn INode>, &'a str)> {
+    let mut dir = root.clone();
+    let mut components = path.split('/').filter(|s| !s.is_empty() && *s != ".");
+    let mut name = components.next().ok_or(FsError::InvalidParam)?;
+    for next in components {
+        dir = match dir.find(name) {
+            Ok(inode) => inode,
+            Err(FsError::EntryNotFound) => dir.create(name, FileType::Dir, 0o755)?,
+            Err(e) => return Err(e),
+        };
+        name = next;
+    }
+    Ok((dir, name))
+}
+
+// 将 cpio 归档的内容解压到 root 目录下
+pub fn unpack(data: &[u8], root: &Arc<dyn INode>) -> Result<()> {
+    let mut pos = 0;
+    while pos + HEADER_SIZE <= data.len() {
+        let header = &data[pos..pos + HEADER_SIZE];
+        if !is_cpio(header) {
+            return Err(FsError::InvalidParam);
+        }
+        let mode = field(header, 1)? as u32;
+        let file_size = field(header, 6)?;
+        let name_size = field(header, 11)?;
+        let name_start = pos + HEADER_SIZE;
+        let data_start = align4(name_start + name_size);
+        let data_end = data_start + file_size;
+        if name_size == 0 || data_end > data.len() {
+            return Err(FsError::InvalidParam);
+        }
+        // 文件名以 \0 结尾
+        let name = str::from_utf8(&data[name_start..name_start + name_size - 1])
+            .map_err(|_| FsError::InvalidParam)?;
+        if name == TRAILER {
+            return Ok(());
+        }
+        let content = &data[data_start..data_end];
+        pos = align4(data_end);
+        if name.split('/').all(|s| s.is_empty() || s == ".") {
+            continue;
+        }
+        let (dir, name) = parent_of(root, name)?;
+        let type_ = match mode & S_IFMT {
+            S_IFDIR => FileType::Dir,
+            S_IFREG => FileType::File,
+            S_IFLNK => FileType::SymLink,
+            _ => {
+                warn!("cpio: skip special file {}", name);
+                continue;
+            }
+        };
+        let inode = match dir.create(name, type_, mode & 0o7777) {
+            Ok(inode) => inode,
+            // 目录可能已经作为某个文件的父目录被创建过了
+            Err(FsError::EntryExist) if type_ == FileType::Dir => continue,
+            Err(e) => return Err(e),
+        };
+        if type_ != FileType::Dir {
+            inode.write_at(0, content)?;
+        }
+    }
+    Ok(())
+}
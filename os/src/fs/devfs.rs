@@ -0,0 +1,231 @@
+use super::tty::TTY;
+use crate::timer::get_cycle;
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::any::Any;
+use lazy_static::*;
+use rcore_fs::vfs::*;
+use spin::Mutex;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Kind {
+    Root,
+    Console,
+    Null,
+    Zero,
+    Urandom,
+}
+
+// (文件名, 类型, 主设备号, 次设备号)，与 Linux 保持一致
+const DEVICES: [(&str, Kind, usize, usize); 4] = [
+    ("console", Kind::Console, 5, 1),
+    ("null", Kind::Null, 1, 3),
+    ("zero", Kind::Zero, 1, 5),
+    ("urandom", Kind::Urandom, 1, 9),
+];
+
+// 挂载在 /dev 上的设备文件系统，目录内容固定
+pub struct DevFs {
+    inodes: Vec<Arc<DevINode>>,
+}
+
+lazy_static! {
+    pub static ref DEVFS: Arc<DevFs> = {
+        let mut inodes = Vec::new();
+        inodes.push(Arc::new(DevINode {
+            id: 1,
+            kind: Kind::Root,
+            rdev: 0,
+        }));
+        for (i, &(_, kind, major, minor)) in DEVICES.iter().enumerate() {
+            inodes.push(Arc::new(DevINode {
+                id: i + 2,
+                kind,
+                rdev: (major << 8) | minor,
+            }));
+        }
+        Arc::new(DevFs { inodes })
+    };
+    static ref RANDOM_STATE: Mutex<u64> = Mutex::new(get_cycle() | 1);
+}
+
+impl FileSystem for DevFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.inodes[0].clone()
+    }
+
+    fn info(&self) -> FsInfo {
+        FsInfo {
+            bsize: 0,
+            frsize: 0,
+            blocks: 0,
+            bfree: 0,
+            bavail: 0,
+            files: self.inodes.len(),
+            ffree: 0,
+            namemax: 255,
+        }
+    }
+}
+
+pub struct DevINode {
+    id: usize,
+    kind: Kind,
+    rdev: usize,
+}
+
+impl DevINode {
+    pub fn is_console(&self) -> bool {
+        self.kind == Kind::Console
+    }
+}
+
+// xorshift64，不适合用于密码学用途
+pub fn fill_random(buf: &mut [u8]) {
+    let mut state = RANDOM_STATE.lock();
+    for chunk in buf.chunks_mut(8) {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        let bytes = state.to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+impl INode for DevINode {
+    fn read_at(&self, _offset: usize, buf: &mut [u8]) -> Result<usize> {
+        match self.kind {
+            Kind::Root => Err(FsError::IsDir),
+            Kind::Console => TTY.read(buf),
+            Kind::Null => Ok(0),
+            Kind::Zero => {
+                buf.iter_mut().for_each(|b| *b = 0);
+                Ok(buf.len())
+            }
+            Kind::Urandom => {
+                fill_random(buf);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn write_at(&self, _offset: usize, buf: &[u8]) -> Result<usize> {
+        match self.kind {
+            Kind::Root => Err(FsError::IsDir),
+            Kind::Console => {
+                TTY.write(buf);
+                Ok(buf.len())
+            }
+            _ => Ok(buf.len()),
+        }
+    }
+
+    fn poll(&self) -> Result<PollStatus> {
+        Ok(PollStatus {
+            read: self.kind != Kind::Console || TTY.readable(),
+            write: true,
+            error: false,
+        })
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let (type_, mode) = match self.kind {
+            Kind::Root => (FileType::Dir, 0o755),
+            _ => (FileType::CharDevice, 0o666),
+        };
+        Ok(Metadata {
+            dev: 0,
+            inode: self.id,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_,
+            mode,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: self.rdev,
+        })
+    }
+
+    fn set_metadata(&self, _metadata: &Metadata) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn resize(&self, _len: usize) -> Result<()> {
+        match self.kind {
+            Kind::Root => Err(FsError::IsDir),
+            _ => Ok(()),
+        }
+    }
+
+    fn create(&self, _name: &str, _type_: FileType, _mode: u32) -> Result<Arc<dyn INode>> {
+        Err(FsError::NotSupported)
+    }
+
+    fn link(&self, _name: &str, _other: &Arc<dyn INode>) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn unlink(&self, _name: &str) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn move_(&self, _old_name: &str, _target: &Arc<dyn INode>, _new_name: &str) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn INode>> {
+        if self.kind != Kind::Root {
+            return Err(FsError::NotDir);
+        }
+        match name {
+            "." | ".." => Ok(DEVFS.root_inode()),
+            name => DEVICES
+                .iter()
+                .position(|&(device, _, _, _)| device == name)
+                .map(|i| DEVFS.inodes[i + 1].clone() as Arc<dyn INode>)
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+
+    fn get_entry(&self, id: usize) -> Result<String> {
+        if self.kind != Kind::Root {
+            return Err(FsError::NotDir);
+        }
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            id => DEVICES
+                .get(id - 2)
+                .map(|&(name, _, _, _)| String::from(name))
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+
+    fn io_control(&self, _cmd: u32, _data: usize) -> Result<usize> {
+        Err(FsError::NotSupported)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        DEVFS.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -1,13 +1,21 @@
 use alloc::sync::Arc;
-use rcore_fs::vfs::INode;
-use rcore_fs_sfs::INodeImpl;
-use crate::fs::ROOT_INODE;
+use rcore_fs::vfs::{FileType, FsError, INode};
+use crate::fs::{lookup_at, lookup_parent};
+
+pub const O_WRONLY: i32 = 1;
+pub const O_RDWR: i32 = 2;
+pub const O_CREAT: i32 = 0o100;
+pub const O_EXCL: i32 = 0o200;
+pub const O_TRUNC: i32 = 0o1000;
+pub const O_APPEND: i32 = 0o2000;
+pub const O_CLOEXEC: i32 = 0o2000000;
 
 #[derive(Copy,Clone,Debug)]
 pub enum FileDescriptorType {
     FD_NONE,
     FD_INODE,
     FD_DEVICE,
+    FD_PIPE,
 }
 
 #[derive(Clone)]
@@ -15,6 +23,7 @@ pub struct File {
     fdtype: FileDescriptorType,
     readable: bool,
     writable: bool,
+    append: bool,
     pub inode: Option<Arc<dyn INode>>,
     offset: usize,
 }
@@ -25,6 +34,7 @@ impl File {
             fdtype: FileDescriptorType::FD_NONE,
             readable: false,
             writable: false,
+            append: false,
             inode: None,
             offset: 0,
         }
@@ -33,23 +43,53 @@ impl File {
     pub fn set_writable(&mut self, v: bool) { self.writable = v; }
     pub fn get_readable(&self) -> bool { self.readable }
     pub fn get_writable(&self) -> bool { self.writable }
+    pub fn set_append(&mut self, v: bool) { self.append = v; }
+    pub fn get_append(&self) -> bool { self.append }
     pub fn set_fdtype(&mut self, t: FileDescriptorType) { self.fdtype = t; }
     pub fn get_fdtype(&self) -> FileDescriptorType { self.fdtype }
     pub fn set_offset(&mut self, o: usize) { self.offset = o; }
     pub fn get_offset(&self) -> usize { self.offset }
 
-    pub fn open_file(&mut self, path: &'static str, flags: i32) {
-        self.set_fdtype(FileDescriptorType::FD_INODE);
-        self.set_readable(true);
-        if (flags & 1) > 0 {
-            self.set_readable(false);
+    // 以 base 为起点打开 path，flags 的含义与 Linux 相同
+    pub fn open_file(&mut self, base: &Arc<dyn INode>, path: &str, flags: i32, mode: u32) -> Result<(), FsError> {
+        let inode = if flags & O_CREAT != 0 {
+            let (dir, name) = lookup_parent(base, path)?;
+            match dir.find(name) {
+                Ok(_) if flags & O_EXCL != 0 => return Err(FsError::EntryExist),
+                Ok(_) => lookup_at(base, path)?,
+                Err(FsError::EntryNotFound) => dir.create(name, FileType::File, mode)?,
+                Err(e) => return Err(e),
+            }
+        } else {
+            lookup_at(base, path)?
+        };
+        let type_ = inode.metadata()?.type_;
+        let writable = flags & (O_WRONLY | O_RDWR) != 0;
+        if type_ == FileType::Dir && writable {
+            return Err(FsError::IsDir);
         }
-        if (flags & 3) > 0 {
-            self.set_writable(true);
+        if flags & O_TRUNC != 0 && writable && type_ == FileType::File {
+            inode.resize(0)?;
         }
-        unsafe {
-            self.inode = Some(ROOT_INODE.lookup(path).unwrap().clone());
+        match type_ {
+            FileType::CharDevice => self.set_fdtype(FileDescriptorType::FD_DEVICE),
+            _ => self.set_fdtype(FileDescriptorType::FD_INODE),
         }
+        self.set_readable(flags & O_WRONLY == 0);
+        self.set_writable(writable);
+        self.append = flags & O_APPEND != 0;
+        self.inode = Some(inode);
         self.set_offset(0);
+        Ok(())
+    }
+
+    // F_GETFL 返回的访问模式与状态标志
+    pub fn get_flags(&self) -> i32 {
+        let mode = match (self.readable, self.writable) {
+            (true, true) => O_RDWR,
+            (false, true) => O_WRONLY,
+            _ => 0,
+        };
+        if self.append { mode | O_APPEND } else { mode }
     }
 }
@@ -1,28 +1,57 @@
+mod cpio;
+pub mod devfs;
 mod device;
-pub mod stdio;
 pub mod file;
+pub mod mount;
+pub mod overlay;
+pub mod pipe;
+pub mod procfs;
+pub mod ramfs;
+pub mod stat;
+pub mod tty;
 
-use alloc::{sync::Arc, vec::Vec};
+use crate::drivers::{board, virtio_blk};
+use crate::memory::access_pa_via_va;
+use alloc::{format, sync::Arc, vec::Vec};
+use core::slice;
+use devfs::DEVFS;
 use lazy_static::*;
+use overlay::OverlayFs;
+use procfs::PROCFS;
+use ramfs::RamFs;
+use rcore_fs::dev::Device;
 use rcore_fs::vfs::*;
 use rcore_fs_sfs::SimpleFileSystem;
 
 lazy_static! {
-    pub static ref ROOT_INODE: Arc<dyn INode> = {
-        let device = {
-            extern "C" {
-                fn _user_img_start();
-                fn _user_img_end();
-            };
-            let start = _user_img_start as usize;
-            let end = _user_img_end as usize;
-            Arc::new(unsafe { device::MemBuf::new(start, end) })
-        };
+    // RamFs 的 inode 只弱引用所属的文件系统，根文件系统需要一直在这里持有
+    pub static ref ROOT_FS: Arc<dyn FileSystem> = {
+        let initrd = board().initrd.map(|(start, end)| unsafe {
+            slice::from_raw_parts(access_pa_via_va(start) as *const u8, end - start)
+        });
+        // initrd 为 cpio 归档时解压到内存文件系统中
+        if let Some(data) = initrd.filter(|data| cpio::is_cpio(data)) {
+            let fs = RamFs::new();
+            cpio::unpack(data, &fs.root_inode()).expect("failed to unpack initramfs");
+            return fs;
+        }
+        // 否则优先使用 virtio 块设备上的 SFS
+        if let Some(device) = virtio_blk::block_device() {
+            let sfs = SimpleFileSystem::open(device).expect("failed to open SFS");
+            return sfs;
+        }
+        // 没有时使用 QEMU -initrd 载入内存的镜像，将其作为只读的下层，写入都落在上层的 tmpfs 中
+        let (start, end) = board().initrd.expect("no root device found!");
+        let device: Arc<dyn Device> =
+            Arc::new(unsafe { device::MemBuf::new(access_pa_via_va(start), access_pa_via_va(end)) });
         let sfs = SimpleFileSystem::open(device).expect("failed to open SFS");
-        sfs.root_inode()
+        OverlayFs::new(sfs, RamFs::new())
     };
+    pub static ref ROOT_INODE: Arc<dyn INode> = ROOT_FS.root_inode();
 }
 
+pub use mount::{lookup, lookup_at, lookup_parent, path_of, sync};
+
 pub trait INodeExt {
     fn read_as_vec(&self) -> Result<Vec<u8>>;
 }
@@ -42,10 +71,26 @@ impl INodeExt for dyn INode {
 pub fn init() {
     println!("available programs in rust/ are:");
     let mut id = 0;
-    let mut rust_dir = ROOT_INODE.lookup("rust").unwrap();
+    let mut rust_dir = lookup("/rust").unwrap();
     while let Ok(name) = rust_dir.get_entry(id) {
         id += 1;
         println!("  {}", name);
     }
+    mount_at("dev", DEVFS.clone());
+    mount_at("proc", PROCFS.clone());
+    mount_at("tmp", RamFs::new());
     println!("++++ setup fs!        ++++")
 }
+
+// 在根目录下的 name 上挂载 fs，根文件系统中没有这个目录时先创建它
+fn mount_at(name: &str, fs: Arc<dyn FileSystem>) {
+    if let Err(FsError::EntryNotFound) = ROOT_INODE.find(name) {
+        if let Err(e) = ROOT_INODE.create(name, FileType::Dir, 0o755) {
+            warn!("failed to create /{}: {:?}", name, e);
+            return;
+        }
+    }
+    if let Err(e) = mount::mount(fs, &format!("/{}", name)) {
+        warn!("failed to mount /{}: {:?}", name, e);
+    }
+}
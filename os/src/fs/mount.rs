@@ -0,0 +1,194 @@
+use super::{ROOT_FS, ROOT_INODE};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use rcore_fs::vfs::*;
+use spin::RwLock;
+
+// 用文件系统对象的地址与 inode 编号唯一确定一个 inode
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct INodeId {
+    fs: usize,
+    inode: usize,
+}
+
+impl INodeId {
+    pub fn of(inode: &Arc<dyn INode>) -> Result<INodeId> {
+        let fs = inode.fs();
+        Ok(INodeId {
+            fs: &*fs as *const dyn FileSystem as *const u8 as usize,
+            inode: inode.metadata()?.inode,
+        })
+    }
+}
+
+struct Mount {
+    fs: Arc<dyn FileSystem>,
+    // 被覆盖的目录，以及挂载的文件系统的根目录
+    mountpoint: Arc<dyn INode>,
+    mountpoint_id: INodeId,
+    root_id: INodeId,
+}
+
+static MOUNTS: RwLock<Vec<Mount>> = RwLock::new(Vec::new());
+
+// 如果 inode 上挂载了文件系统，返回最后挂载的那个文件系统的根目录
+fn cross_mountpoint(inode: Arc<dyn INode>) -> Result<Arc<dyn INode>> {
+    let mut inode = inode;
+    loop {
+        let id = INodeId::of(&inode)?;
+        let mounts = MOUNTS.read();
+        match mounts.iter().rev().find(|m| m.mountpoint_id == id) {
+            Some(mount) => {
+                let root = mount.fs.root_inode();
+                drop(mounts);
+                inode = root;
+            }
+            None => return Ok(inode),
+        }
+    }
+}
+
+fn parent(inode: Arc<dyn INode>) -> Result<Arc<dyn INode>> {
+    let mut inode = inode;
+    loop {
+        let id = INodeId::of(&inode)?;
+        if id == INodeId::of(&ROOT_INODE)? {
+            return cross_mountpoint(inode);
+        }
+        // 在挂载的文件系统的根目录中，.. 指向挂载点的父目录
+        let mountpoint = MOUNTS
+            .read()
+            .iter()
+            .rev()
+            .find(|m| m.root_id == id)
+            .map(|m| m.mountpoint.clone());
+        match mountpoint {
+            Some(mountpoint) => inode = mountpoint,
+            None => return cross_mountpoint(inode.find("..")?),
+        }
+    }
+}
+
+// 从 base 开始解析路径，以 / 开头的路径从根目录开始
+pub fn lookup_at(base: &Arc<dyn INode>, path: &str) -> Result<Arc<dyn INode>> {
+    let mut inode = if path.starts_with('/') {
+        cross_mountpoint(ROOT_INODE.clone())?
+    } else {
+        base.clone()
+    };
+    for name in path.split('/') {
+        match name {
+            "" | "." => {}
+            ".." => inode = parent(inode)?,
+            name => inode = cross_mountpoint(inode.find(name)?)?,
+        }
+    }
+    Ok(inode)
+}
+
+// 解析路径中除最后一项以外的部分，返回其所在目录与最后一项的名字
+pub fn lookup_parent<'a>(
+    base: &Arc<dyn INode>,
+    path: &'a str,
+) -> Result<(Arc<dyn INode>, &'a str)> {
+    let path = path.trim_end_matches('/');
+    let (dir, name) = match path.rfind('/') {
+        Some(0) => ("/", &path[1..]),
+        Some(i) => (&path[..i], &path[i + 1..]),
+        None => ("", path),
+    };
+    if name.is_empty() {
+        return Err(FsError::InvalidParam);
+    }
+    let dir = lookup_at(base, dir)?;
+    if dir.metadata()?.type_ != FileType::Dir {
+        return Err(FsError::NotDir);
+    }
+    Ok((dir, name))
+}
+
+pub fn lookup(path: &str) -> Result<Arc<dyn INode>> {
+    lookup_at(&ROOT_INODE, path)
+}
+
+// 从 inode 逐级向上，在父目录中找到指向自己的目录项，拼出绝对路径
+pub fn path_of(inode: &Arc<dyn INode>) -> Result<String> {
+    let root_id = INodeId::of(&cross_mountpoint(ROOT_INODE.clone())?)?;
+    let mut inode = inode.clone();
+    let mut names = Vec::new();
+    loop {
+        let id = INodeId::of(&inode)?;
+        if id == root_id {
+            break;
+        }
+        let parent = parent(inode)?;
+        if INodeId::of(&parent)? == id {
+            break;
+        }
+        let mut entry = 2;
+        let name = loop {
+            let name = parent.get_entry(entry)?;
+            entry += 1;
+            let child = match parent.find(&name).and_then(cross_mountpoint) {
+                Ok(child) => child,
+                Err(_) => continue,
+            };
+            if INodeId::of(&child)? == id {
+                break name;
+            }
+        };
+        names.push(name);
+        inode = parent;
+    }
+    let mut path = String::new();
+    for name in names.iter().rev() {
+        path.push('/');
+        path.push_str(name);
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    Ok(path)
+}
+
+// 将 fs 挂载到 path 所指的目录上
+pub fn mount(fs: Arc<dyn FileSystem>, path: &str) -> Result<()> {
+    let mountpoint = lookup(path)?;
+    if mountpoint.metadata()?.type_ != FileType::Dir {
+        return Err(FsError::NotDir);
+    }
+    let mount = Mount {
+        mountpoint_id: INodeId::of(&mountpoint)?,
+        root_id: INodeId::of(&fs.root_inode())?,
+        mountpoint,
+        fs,
+    };
+    MOUNTS.write().push(mount);
+    Ok(())
+}
+
+// 卸载挂载在 path 上的文件系统，其上还有其它挂载时返回 Busy
+pub fn umount(path: &str) -> Result<()> {
+    let id = INodeId::of(&lookup(path)?)?;
+    let mut mounts = MOUNTS.write();
+    let index = mounts
+        .iter()
+        .rposition(|m| m.root_id == id)
+        .ok_or(FsError::InvalidParam)?;
+    if mounts
+        .iter()
+        .any(|m| m.mountpoint_id.fs == mounts[index].root_id.fs)
+    {
+        return Err(FsError::Busy);
+    }
+    mounts[index].fs.sync()?;
+    mounts.remove(index);
+    Ok(())
+}
+
+// 写回根文件系统与所有挂载的文件系统，返回遇到的第一个错误
+pub fn sync() -> Result<()> {
+    let mounted: Vec<Arc<dyn FileSystem>> = MOUNTS.read().iter().map(|m| m.fs.clone()).collect();
+    mounted
+        .iter()
+        .fold(ROOT_FS.sync(), |result, fs| result.and(fs.sync()))
+}
@@ -0,0 +1,404 @@
+use super::INodeExt;
+use alloc::{
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::any::Any;
+use rcore_fs::vfs::*;
+use spin::{Once, RwLock};
+
+// 上层目录中的白化项 (whiteout) 为设备号为 0 的字符设备，表示下层的同名文件已被删除
+// 目录中存在 OPAQUE 项时，下层同名目录的内容被完全遮盖
+const OPAQUE: &str = ".wh..opq";
+// 只存在于上层的 inode，编号加上这个偏移以免与下层冲突
+const UPPER_INODE_OFFSET: usize = 1 << 40;
+
+fn is_whiteout(inode: &Arc<dyn INode>) -> bool {
+    match inode.metadata() {
+        Ok(metadata) => metadata.type_ == FileType::CharDevice && metadata.rdev == 0,
+        Err(_) => false,
+    }
+}
+
+// 只读的下层与可写的上层 (通常是 tmpfs) 叠加而成的文件系统
+pub struct OverlayFs {
+    lower: Arc<dyn FileSystem>,
+    upper: Arc<dyn FileSystem>,
+    this: Once<Weak<OverlayFs>>,
+}
+
+impl OverlayFs {
+    pub fn new(lower: Arc<dyn FileSystem>, upper: Arc<dyn FileSystem>) -> Arc<Self> {
+        let fs = Arc::new(OverlayFs {
+            lower,
+            upper,
+            this: Once::new(),
+        });
+        fs.this.call_once(|| Arc::downgrade(&fs));
+        fs
+    }
+}
+
+impl FileSystem for OverlayFs {
+    fn sync(&self) -> Result<()> {
+        self.upper.sync()
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        Arc::new(OverlayINode {
+            fs: self.this.r#try().unwrap().upgrade().unwrap(),
+            parent: None,
+            name: String::new(),
+            lower: Some(self.lower.root_inode()),
+            upper: RwLock::new(Some(self.upper.root_inode())),
+        })
+    }
+
+    fn info(&self) -> FsInfo {
+        self.upper.info()
+    }
+}
+
+// OverlayFs 不缓存 inode，inode 持有文件系统的强引用不会形成环，
+// 只持有根目录的调用者也能一直使用它
+pub struct OverlayINode {
+    fs: Arc<OverlayFs>,
+    parent: Option<Arc<OverlayINode>>,
+    name: String,
+    lower: Option<Arc<dyn INode>>,
+    // 第一次修改时才会在上层创建 (copy-up)
+    upper: RwLock<Option<Arc<dyn INode>>>,
+}
+
+impl OverlayINode {
+    fn this(&self) -> Arc<OverlayINode> {
+        Arc::new(OverlayINode {
+            fs: self.fs.clone(),
+            parent: self.parent.clone(),
+            name: self.name.clone(),
+            lower: self.lower.clone(),
+            upper: RwLock::new(self.upper()),
+        })
+    }
+
+    // 上层的 inode，可能已经由另一个指向同一文件的 OverlayINode 创建
+    fn upper(&self) -> Option<Arc<dyn INode>> {
+        if let Some(upper) = self.upper.read().clone() {
+            return Some(upper);
+        }
+        let upper = self
+            .parent
+            .as_ref()?
+            .upper()?
+            .find(&self.name)
+            .ok()
+            .filter(|inode| !is_whiteout(inode))?;
+        *self.upper.write() = Some(upper.clone());
+        Some(upper)
+    }
+
+    fn real(&self) -> Arc<dyn INode> {
+        self.upper()
+            .or_else(|| self.lower.clone())
+            .expect("overlay inode without any layer!")
+    }
+
+    fn is_dir(&self) -> bool {
+        self.real().metadata().map(|m| m.type_) == Ok(FileType::Dir)
+    }
+
+    fn is_opaque(&self) -> bool {
+        match self.upper() {
+            Some(upper) => upper.find(OPAQUE).is_ok(),
+            None => false,
+        }
+    }
+
+    // 保证上层存在这个 inode，必要时先复制父目录
+    fn copy_up(&self) -> Result<Arc<dyn INode>> {
+        if let Some(upper) = self.upper() {
+            return Ok(upper);
+        }
+        let parent = self.parent.as_ref().unwrap().copy_up()?;
+        let lower = self.lower.as_ref().unwrap();
+        let metadata = lower.metadata()?;
+        let upper = parent.create(&self.name, metadata.type_, metadata.mode as u32)?;
+        if metadata.type_ != FileType::Dir {
+            upper.write_at(0, &lower.read_as_vec()?)?;
+        }
+        *self.upper.write() = Some(upper.clone());
+        Ok(upper)
+    }
+
+    fn child(&self, name: &str) -> Result<Arc<OverlayINode>> {
+        if !self.is_dir() {
+            return Err(FsError::NotDir);
+        }
+        let mut upper = None;
+        if let Some(dir) = self.upper() {
+            match dir.find(name) {
+                Ok(inode) if is_whiteout(&inode) => return Err(FsError::EntryNotFound),
+                Ok(inode) => upper = Some(inode),
+                Err(FsError::EntryNotFound) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let mut lower = None;
+        if !self.is_opaque() {
+            if let Some(dir) = self.lower.as_ref() {
+                lower = dir.find(name).ok();
+            }
+        }
+        // 上层是普通文件时下层的同名项被遮盖
+        if let (Some(u), Some(l)) = (upper.as_ref(), lower.as_ref()) {
+            let is_dir =
+                |inode: &Arc<dyn INode>| inode.metadata().map(|m| m.type_) == Ok(FileType::Dir);
+            if !is_dir(u) || !is_dir(l) {
+                lower = None;
+            }
+        }
+        if upper.is_none() && lower.is_none() {
+            return Err(FsError::EntryNotFound);
+        }
+        Ok(Arc::new(OverlayINode {
+            fs: self.fs.clone(),
+            parent: Some(self.this()),
+            name: String::from(name),
+            lower,
+            upper: RwLock::new(upper),
+        }))
+    }
+
+    fn entries(&self) -> Result<Vec<String>> {
+        if !self.is_dir() {
+            return Err(FsError::NotDir);
+        }
+        let mut entries = Vec::new();
+        let mut whiteouts = Vec::new();
+        if let Some(upper) = self.upper() {
+            let mut id = 2;
+            while let Ok(name) = upper.get_entry(id) {
+                id += 1;
+                if name == OPAQUE {
+                    continue;
+                }
+                match upper.find(&name) {
+                    Ok(inode) if is_whiteout(&inode) => whiteouts.push(name),
+                    _ => entries.push(name),
+                }
+            }
+        }
+        if !self.is_opaque() {
+            if let Some(lower) = self.lower.as_ref() {
+                let mut id = 2;
+                while let Ok(name) = lower.get_entry(id) {
+                    id += 1;
+                    if !entries.contains(&name) && !whiteouts.contains(&name) {
+                        entries.push(name);
+                    }
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    // 在上层目录中删除 name，下层存在同名项时留下白化项
+    fn remove(&self, name: &str, child: &OverlayINode) -> Result<()> {
+        let upper = self.copy_up()?;
+        if let Some(child_upper) = child.upper() {
+            // 合并后为空的目录中只可能剩下白化项
+            let mut id = 2;
+            while let Ok(entry) = child_upper.get_entry(id) {
+                if child_upper.unlink(&entry).is_err() {
+                    id += 1;
+                }
+            }
+            upper.unlink(name)?;
+        }
+        if child.lower.is_some() {
+            upper.create(name, FileType::CharDevice, 0)?;
+        }
+        Ok(())
+    }
+
+    // 在上层目录中准备好名字 name，去掉可能存在的白化项，返回创建的项是否需要遮盖下层
+    fn prepare_name(&self, upper: &Arc<dyn INode>, name: &str) -> Result<bool> {
+        match upper.find(name) {
+            Ok(inode) if is_whiteout(&inode) => {
+                upper.unlink(name)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+impl INode for OverlayINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        self.real().read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        self.copy_up()?.write_at(offset, buf)
+    }
+
+    fn poll(&self) -> Result<PollStatus> {
+        self.real().poll()
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let mut metadata = self.real().metadata()?;
+        metadata.inode = match self.lower.as_ref() {
+            Some(lower) => lower.metadata()?.inode,
+            None => metadata.inode + UPPER_INODE_OFFSET,
+        };
+        Ok(metadata)
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> Result<()> {
+        self.copy_up()?.set_metadata(metadata)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        match self.upper() {
+            Some(upper) => upper.sync_all(),
+            None => Ok(()),
+        }
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        match self.upper() {
+            Some(upper) => upper.sync_data(),
+            None => Ok(()),
+        }
+    }
+
+    fn resize(&self, len: usize) -> Result<()> {
+        self.copy_up()?.resize(len)
+    }
+
+    fn create(&self, name: &str, type_: FileType, mode: u32) -> Result<Arc<dyn INode>> {
+        match self.child(name) {
+            Ok(_) => return Err(FsError::EntryExist),
+            Err(FsError::EntryNotFound) => {}
+            Err(e) => return Err(e),
+        }
+        let upper = self.copy_up()?;
+        let opaque = self.prepare_name(&upper, name)?;
+        let inode = upper.create(name, type_, mode)?;
+        if opaque && type_ == FileType::Dir {
+            inode.create(OPAQUE, FileType::CharDevice, 0)?;
+        }
+        Ok(self.child(name)?)
+    }
+
+    fn link(&self, name: &str, other: &Arc<dyn INode>) -> Result<()> {
+        let other = other
+            .downcast_ref::<OverlayINode>()
+            .ok_or(FsError::NotSameFs)?;
+        if !Arc::ptr_eq(&self.fs, &other.fs) {
+            return Err(FsError::NotSameFs);
+        }
+        match self.child(name) {
+            Ok(_) => return Err(FsError::EntryExist),
+            Err(FsError::EntryNotFound) => {}
+            Err(e) => return Err(e),
+        }
+        let other = other.copy_up()?;
+        let upper = self.copy_up()?;
+        self.prepare_name(&upper, name)?;
+        upper.link(name, &other)
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        if name == "." || name == ".." {
+            return Err(FsError::InvalidParam);
+        }
+        let child = self.child(name)?;
+        if child.is_dir() && !child.entries()?.is_empty() {
+            return Err(FsError::DirNotEmpty);
+        }
+        self.remove(name, &child)
+    }
+
+    fn move_(&self, old_name: &str, target: &Arc<dyn INode>, new_name: &str) -> Result<()> {
+        let target = target
+            .downcast_ref::<OverlayINode>()
+            .ok_or(FsError::NotSameFs)?;
+        if !Arc::ptr_eq(&self.fs, &target.fs) {
+            return Err(FsError::NotSameFs);
+        }
+        let child = self.child(old_name)?;
+        if old_name == new_name && self.metadata()?.inode == target.metadata()?.inode {
+            return Ok(());
+        }
+        // 与 Linux 的 overlayfs 一样，不支持移动含有下层内容的目录
+        if child.is_dir() && child.lower.is_some() {
+            return Err(FsError::NotSameFs);
+        }
+        let child_upper = child.copy_up()?;
+        match target.child(new_name) {
+            Ok(existing) => {
+                match (child.is_dir(), existing.is_dir()) {
+                    (true, false) => return Err(FsError::NotDir),
+                    (false, true) => return Err(FsError::IsDir),
+                    (true, true) if !existing.entries()?.is_empty() => {
+                        return Err(FsError::DirNotEmpty)
+                    }
+                    _ => {}
+                }
+                target.remove(new_name, &existing)?;
+            }
+            Err(FsError::EntryNotFound) => {}
+            Err(e) => return Err(e),
+        }
+        let upper = self.copy_up()?;
+        let target_upper = target.copy_up()?;
+        let opaque = target.prepare_name(&target_upper, new_name)?;
+        upper.move_(old_name, &target_upper, new_name)?;
+        if opaque && child.is_dir() {
+            child_upper.create(OPAQUE, FileType::CharDevice, 0)?;
+        }
+        if child.lower.is_some() {
+            upper.create(old_name, FileType::CharDevice, 0)?;
+        }
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn INode>> {
+        match name {
+            "." => Ok(self.this()),
+            ".." => match self.parent.as_ref() {
+                Some(parent) => Ok(parent.this()),
+                None => Ok(self.this()),
+            },
+            OPAQUE => Err(FsError::EntryNotFound),
+            name => Ok(self.child(name)?),
+        }
+    }
+
+    fn get_entry(&self, id: usize) -> Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            id => self
+                .entries()?
+                .get(id - 2)
+                .cloned()
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> Result<usize> {
+        self.real().io_control(cmd, data)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,260 @@
+use super::ramfs::RamFs;
+use crate::interrupt::{disable_and_store, restore};
+use crate::process;
+use crate::sync::condvar::Condvar;
+use alloc::{collections::VecDeque, string::String, sync::Arc};
+use core::any::Any;
+use lazy_static::*;
+use rcore_fs::vfs::*;
+use spin::Mutex;
+
+pub const PIPE_SIZE: usize = 4096;
+
+// 管道所属的匿名文件系统，不会被挂载，根目录是一个空的 RamFs 目录
+pub struct PipeFs {
+    root: Arc<RamFs>,
+}
+
+lazy_static! {
+    static ref PIPE_FS: Arc<PipeFs> = Arc::new(PipeFs { root: RamFs::new() });
+}
+
+impl FileSystem for PipeFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.root.root_inode()
+    }
+
+    fn info(&self) -> FsInfo {
+        FsInfo {
+            bsize: PIPE_SIZE,
+            frsize: PIPE_SIZE,
+            blocks: 0,
+            bfree: 0,
+            bavail: 0,
+            files: 0,
+            ffree: 0,
+            namemax: 0,
+        }
+    }
+}
+
+struct PipeInner {
+    buf: VecDeque<u8>,
+    readers: usize,
+    writers: usize,
+}
+
+// 有界的环形缓冲区，读端在为空时、写端在满时睡眠
+pub struct Pipe {
+    inner: Mutex<PipeInner>,
+    readable: Condvar,
+    writable: Condvar,
+}
+
+// 管道的一端，最后一个引用被释放时即关闭
+pub struct PipeEnd {
+    pipe: Arc<Pipe>,
+    write: bool,
+}
+
+impl Pipe {
+    // 返回 (读端, 写端)
+    pub fn new() -> (Arc<PipeEnd>, Arc<PipeEnd>) {
+        let pipe = Arc::new(Pipe {
+            inner: Mutex::new(PipeInner {
+                buf: VecDeque::with_capacity(PIPE_SIZE),
+                readers: 1,
+                writers: 1,
+            }),
+            readable: Condvar::new(),
+            writable: Condvar::new(),
+        });
+        let read_end = Arc::new(PipeEnd {
+            pipe: pipe.clone(),
+            write: false,
+        });
+        let write_end = Arc::new(PipeEnd { pipe, write: true });
+        (read_end, write_end)
+    }
+}
+
+impl PipeEnd {
+    // 至少读到一个字节才返回，所有写端都关闭后返回 0，有信号待处理时返回 Interrupted
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            // 检查与加入等待队列之间不能被中断，否则可能错过写端的唤醒
+            let flags = disable_and_store();
+            let mut inner = self.pipe.inner.lock();
+            if !inner.buf.is_empty() {
+                let len = buf.len().min(inner.buf.len());
+                for (dst, src) in buf.iter_mut().zip(inner.buf.drain(..len)) {
+                    *dst = src;
+                }
+                drop(inner);
+                restore(flags);
+                self.pipe.writable.notify_all();
+                return Ok(len);
+            }
+            let writers = inner.writers;
+            drop(inner);
+            if writers == 0 {
+                restore(flags);
+                return Ok(0);
+            }
+            if process::signal_pending() {
+                restore(flags);
+                return Err(FsError::Interrupted);
+            }
+            self.pipe.readable.wait_restore(flags);
+        }
+    }
+
+    // 写完全部数据才返回，所有读端都已关闭时返回 None；
+    // 有信号待处理时返回已写入的长度，还未写入时返回 Interrupted
+    pub fn write(&self, buf: &[u8]) -> Result<Option<usize>> {
+        let mut written = 0;
+        while written < buf.len() {
+            let flags = disable_and_store();
+            let mut inner = self.pipe.inner.lock();
+            if inner.readers == 0 {
+                drop(inner);
+                restore(flags);
+                return Ok(None);
+            }
+            let len = (buf.len() - written).min(PIPE_SIZE - inner.buf.len());
+            inner.buf.extend(&buf[written..written + len]);
+            written += len;
+            drop(inner);
+            if len > 0 {
+                restore(flags);
+                self.pipe.readable.notify_all();
+            } else if process::signal_pending() {
+                restore(flags);
+                return match written {
+                    0 => Err(FsError::Interrupted),
+                    _ => Ok(Some(written)),
+                };
+            } else {
+                self.pipe.writable.wait_restore(flags);
+            }
+        }
+        Ok(Some(written))
+    }
+}
+
+impl Drop for PipeEnd {
+    fn drop(&mut self) {
+        let mut inner = self.pipe.inner.lock();
+        if self.write {
+            inner.writers -= 1;
+        } else {
+            inner.readers -= 1;
+        }
+        drop(inner);
+        self.pipe.readable.notify_all();
+        self.pipe.writable.notify_all();
+    }
+}
+
+impl INode for PipeEnd {
+    fn read_at(&self, _offset: usize, buf: &mut [u8]) -> Result<usize> {
+        match self.write {
+            false => self.read(buf),
+            true => Err(FsError::NotSupported),
+        }
+    }
+
+    fn write_at(&self, _offset: usize, buf: &[u8]) -> Result<usize> {
+        match self.write {
+            true => self.write(buf)?.ok_or(FsError::NotSupported),
+            false => Err(FsError::NotSupported),
+        }
+    }
+
+    fn poll(&self) -> Result<PollStatus> {
+        let inner = self.pipe.inner.lock();
+        Ok(PollStatus {
+            read: !self.write && (!inner.buf.is_empty() || inner.writers == 0),
+            write: self.write && (inner.buf.len() < PIPE_SIZE || inner.readers == 0),
+            error: false,
+        })
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        Ok(Metadata {
+            dev: 0,
+            inode: &*self.pipe as *const Pipe as usize,
+            size: self.pipe.inner.lock().buf.len(),
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: FileType::NamedPipe,
+            mode: 0o600,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn set_metadata(&self, _metadata: &Metadata) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn resize(&self, _len: usize) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn create(&self, _name: &str, _type_: FileType, _mode: u32) -> Result<Arc<dyn INode>> {
+        Err(FsError::NotDir)
+    }
+
+    fn link(&self, _name: &str, _other: &Arc<dyn INode>) -> Result<()> {
+        Err(FsError::NotDir)
+    }
+
+    fn unlink(&self, _name: &str) -> Result<()> {
+        Err(FsError::NotDir)
+    }
+
+    fn move_(&self, _old_name: &str, _target: &Arc<dyn INode>, _new_name: &str) -> Result<()> {
+        Err(FsError::NotDir)
+    }
+
+    fn find(&self, _name: &str) -> Result<Arc<dyn INode>> {
+        Err(FsError::NotDir)
+    }
+
+    fn get_entry(&self, _id: usize) -> Result<String> {
+        Err(FsError::NotDir)
+    }
+
+    fn io_control(&self, _cmd: u32, _data: usize) -> Result<usize> {
+        Err(FsError::NotSupported)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        PIPE_FS.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,286 @@
+use crate::fs::file::File;
+use crate::fs::path_of;
+use crate::memory::{frame_stats, heap_stats};
+use crate::process::{self, structs::Status, Tid};
+use crate::timer::{TICKS, TICKS_PER_SECOND};
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+use core::any::Any;
+use core::fmt::Write;
+use lazy_static::*;
+use rcore_fs::vfs::*;
+
+// 挂载在 /proc 上的文件系统，文件内容在读取时生成
+pub struct ProcFs;
+
+lazy_static! {
+    pub static ref PROCFS: Arc<ProcFs> = Arc::new(ProcFs);
+}
+
+impl FileSystem for ProcFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        Arc::new(ProcINode(Node::Root))
+    }
+
+    fn info(&self) -> FsInfo {
+        FsInfo {
+            bsize: 0,
+            frsize: 0,
+            blocks: 0,
+            bfree: 0,
+            bavail: 0,
+            files: 0,
+            ffree: 0,
+            namemax: 255,
+        }
+    }
+}
+
+const GLOBAL_FILES: [&str; 3] = ["meminfo", "uptime", "sched"];
+const THREAD_FILES: [&str; 3] = ["status", "maps", "fds"];
+
+// inode 编号: 根目录为 1，全局文件从 2 开始，线程 tid 的目录与文件从 (tid + 1) << 4 开始
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Node {
+    Root,
+    Global(usize),
+    Thread(Tid),
+    ThreadFile(Tid, usize),
+}
+
+pub struct ProcINode(Node);
+
+impl ProcINode {
+    fn id(&self) -> usize {
+        match self.0 {
+            Node::Root => 1,
+            Node::Global(i) => i + 2,
+            Node::Thread(tid) => (tid + 1) << 4,
+            Node::ThreadFile(tid, i) => ((tid + 1) << 4) + i + 1,
+        }
+    }
+
+    fn content(&self) -> Result<String> {
+        match self.0 {
+            Node::Root | Node::Thread(_) => Err(FsError::IsDir),
+            Node::Global(i) => Ok(match GLOBAL_FILES[i] {
+                "meminfo" => meminfo(),
+                "uptime" => uptime(),
+                _ => format!("{}\n", process::scheduler_name()),
+            }),
+            Node::ThreadFile(tid, i) => process::with_thread(tid, |status, thread| {
+                let mut s = String::new();
+                match THREAD_FILES[i] {
+                    "status" => {
+                        let state = match status {
+                            Status::Ready => "ready",
+                            Status::Running(_) => "running",
+                            Status::Sleeping => "sleeping",
+                            Status::Stopped(_) => "stopped",
+                            Status::Exited(_) => "exited",
+                        };
+                        writeln!(s, "tid:\t{}", tid).unwrap();
+                        writeln!(s, "state:\t{}", state).unwrap();
+                        if let Some(wait) = thread.wait {
+                            writeln!(s, "wait:\t{}", wait).unwrap();
+                        }
+                        writeln!(s, "pgid:\t{}", thread.pgid).unwrap();
+                        writeln!(s, "sid:\t{}", thread.sid).unwrap();
+                        if let Some(cwd) = thread.cwd.as_ref() {
+                            if let Ok(path) = path_of(cwd) {
+                                writeln!(s, "cwd:\t{}", path).unwrap();
+                            }
+                        }
+                    }
+                    "maps" => {
+                        if let Some(vm) = thread.vm.as_ref() {
+                            for area in vm.lock().areas() {
+                                let (start, end) = area.range();
+                                writeln!(
+                                    s,
+                                    "{:#018x}-{:#018x} {} {:?}",
+                                    start,
+                                    end,
+                                    area.attr(),
+                                    area.handler()
+                                )
+                                .unwrap();
+                            }
+                        }
+                    }
+                    _ => {
+                        for (fd, file) in thread.files.lock().iter() {
+                            writeln!(s, "{}\t{}", fd, describe(&file.lock())).unwrap();
+                        }
+                    }
+                }
+                s
+            })
+            .ok_or(FsError::EntryNotFound),
+        }
+    }
+}
+
+fn describe(file: &File) -> String {
+    format!(
+        "{:?} {}{} offset={}",
+        file.get_fdtype(),
+        if file.get_readable() { 'r' } else { '-' },
+        if file.get_writable() { 'w' } else { '-' },
+        file.get_offset()
+    )
+}
+
+fn meminfo() -> String {
+    let (frames, free_frames) = frame_stats();
+    let (heap_total, heap_used) = heap_stats();
+    format!(
+        "FrameTotal:\t{} kB\nFrameFree:\t{} kB\nHeapTotal:\t{} kB\nHeapUsed:\t{} kB\n",
+        frames * 4,
+        free_frames * 4,
+        heap_total / 1024,
+        heap_used / 1024
+    )
+}
+
+fn uptime() -> String {
+    let ticks = unsafe { TICKS };
+    format!(
+        "{}.{:02}\n",
+        ticks / TICKS_PER_SECOND,
+        ticks % TICKS_PER_SECOND * 100 / TICKS_PER_SECOND
+    )
+}
+
+impl INode for ProcINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let content = self.content()?;
+        let content = content.as_bytes();
+        if offset >= content.len() {
+            return Ok(0);
+        }
+        let len = buf.len().min(content.len() - offset);
+        buf[..len].copy_from_slice(&content[offset..offset + len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize> {
+        Err(FsError::NotSupported)
+    }
+
+    fn poll(&self) -> Result<PollStatus> {
+        Ok(PollStatus {
+            read: true,
+            write: false,
+            error: false,
+        })
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let (type_, mode, size) = match self.0 {
+            Node::Root | Node::Thread(_) => (FileType::Dir, 0o555, 0),
+            _ => (FileType::File, 0o444, self.content()?.len()),
+        };
+        Ok(Metadata {
+            dev: 0,
+            inode: self.id(),
+            size,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_,
+            mode,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn set_metadata(&self, _metadata: &Metadata) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn resize(&self, _len: usize) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn create(&self, _name: &str, _type_: FileType, _mode: u32) -> Result<Arc<dyn INode>> {
+        Err(FsError::NotSupported)
+    }
+
+    fn link(&self, _name: &str, _other: &Arc<dyn INode>) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn unlink(&self, _name: &str) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn move_(&self, _old_name: &str, _target: &Arc<dyn INode>, _new_name: &str) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn INode>> {
+        let node = match (self.0, name) {
+            (Node::Root, ".") | (Node::Root, "..") | (Node::Thread(_), "..") => Node::Root,
+            (Node::Thread(tid), ".") => Node::Thread(tid),
+            (Node::Root, name) => match GLOBAL_FILES.iter().position(|&f| f == name) {
+                Some(i) => Node::Global(i),
+                None => {
+                    let tid = name.parse::<Tid>().map_err(|_| FsError::EntryNotFound)?;
+                    if !process::tids().contains(&tid) {
+                        return Err(FsError::EntryNotFound);
+                    }
+                    Node::Thread(tid)
+                }
+            },
+            (Node::Thread(tid), name) => match THREAD_FILES.iter().position(|&f| f == name) {
+                Some(i) => Node::ThreadFile(tid, i),
+                None => return Err(FsError::EntryNotFound),
+            },
+            _ => return Err(FsError::NotDir),
+        };
+        Ok(Arc::new(ProcINode(node)))
+    }
+
+    fn get_entry(&self, id: usize) -> Result<String> {
+        let mut entries: Vec<String> = Vec::new();
+        entries.push(String::from("."));
+        entries.push(String::from(".."));
+        match self.0 {
+            Node::Root => {
+                entries.extend(GLOBAL_FILES.iter().map(|&f| String::from(f)));
+                entries.extend(process::tids().iter().map(|tid| format!("{}", tid)));
+            }
+            Node::Thread(_) => entries.extend(THREAD_FILES.iter().map(|&f| String::from(f))),
+            _ => return Err(FsError::NotDir),
+        }
+        entries.get(id).cloned().ok_or(FsError::EntryNotFound)
+    }
+
+    fn io_control(&self, _cmd: u32, _data: usize) -> Result<usize> {
+        Err(FsError::NotSupported)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        PROCFS.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
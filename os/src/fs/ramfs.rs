@@ -0,0 +1,501 @@
+use crate::consts::PAGE_SIZE;
+use crate::memory::{access_pa_via_va, alloc_frame, dealloc_frame};
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use core::any::Any;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use rcore_fs::vfs::*;
+use riscv::addr::Frame;
+use spin::RwLock;
+
+// 完全位于内存中的文件系统，内容在关机后丢失
+pub struct RamFs {
+    root: Arc<RamINode>,
+    next_inode: AtomicUsize,
+}
+
+impl RamFs {
+    pub fn new() -> Arc<Self> {
+        let root = Arc::new(RamINode::new(1, FileType::Dir, 0o755));
+        let fs = Arc::new(RamFs {
+            root,
+            next_inode: AtomicUsize::new(2),
+        });
+        {
+            let mut root = fs.root.inner.write();
+            root.this = Arc::downgrade(&fs.root);
+            root.parent = Arc::downgrade(&fs.root);
+            root.fs = Arc::downgrade(&fs);
+        }
+        fs
+    }
+}
+
+impl FileSystem for RamFs {
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.root.clone()
+    }
+
+    fn info(&self) -> FsInfo {
+        FsInfo {
+            bsize: BLOCK_SIZE,
+            frsize: BLOCK_SIZE,
+            blocks: 0,
+            bfree: 0,
+            bavail: 0,
+            files: self.next_inode.load(Ordering::Relaxed) - 1,
+            ffree: 0,
+            namemax: MAX_NAME_LEN,
+        }
+    }
+}
+
+const BLOCK_SIZE: usize = 4096;
+const MAX_NAME_LEN: usize = 255;
+
+// 文件内容放在按需分配的物理页帧中而不是内核堆上，页帧用完时写入失败
+// 最后一页中 len 之后的部分总是为 0
+struct Content {
+    frames: Vec<Frame>,
+    len: usize,
+}
+
+impl Content {
+    fn new() -> Self {
+        Content {
+            frames: Vec::new(),
+            len: 0,
+        }
+    }
+
+    fn page(&self, i: usize) -> &[u8] {
+        let va = access_pa_via_va(self.frames[i].start_address().as_usize());
+        unsafe { core::slice::from_raw_parts(va as *const u8, PAGE_SIZE) }
+    }
+
+    fn page_mut(&mut self, i: usize) -> &mut [u8] {
+        let va = access_pa_via_va(self.frames[i].start_address().as_usize());
+        unsafe { core::slice::from_raw_parts_mut(va as *mut u8, PAGE_SIZE) }
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        if offset >= self.len {
+            return 0;
+        }
+        let len = buf.len().min(self.len - offset);
+        for (i, start, done, n) in page_chunks(offset, len) {
+            buf[done..done + n].copy_from_slice(&self.page(i)[start..start + n]);
+        }
+        len
+    }
+
+    fn write_at(&mut self, offset: usize, buf: &[u8]) -> Result<usize> {
+        let end = offset
+            .checked_add(buf.len())
+            .ok_or(FsError::NoDeviceSpace)?;
+        if end > self.len {
+            self.resize(end)?;
+        }
+        for (i, start, done, n) in page_chunks(offset, buf.len()) {
+            self.page_mut(i)[start..start + n].copy_from_slice(&buf[done..done + n]);
+        }
+        Ok(buf.len())
+    }
+
+    // 页帧不足时返回 NoDeviceSpace，内容不变
+    fn resize(&mut self, len: usize) -> Result<()> {
+        let pages = len / PAGE_SIZE + (len % PAGE_SIZE != 0) as usize;
+        let old_pages = self.frames.len();
+        while self.frames.len() < pages {
+            match alloc_frame() {
+                Some(frame) => {
+                    self.frames.push(frame);
+                    let last = self.frames.len() - 1;
+                    self.page_mut(last).iter_mut().for_each(|b| *b = 0);
+                }
+                None => {
+                    self.release(old_pages);
+                    return Err(FsError::NoDeviceSpace);
+                }
+            }
+        }
+        self.release(pages);
+        if len < self.len && len % PAGE_SIZE != 0 {
+            self.page_mut(len / PAGE_SIZE)[len % PAGE_SIZE..]
+                .iter_mut()
+                .for_each(|b| *b = 0);
+        }
+        self.len = len;
+        Ok(())
+    }
+
+    // 只保留前 pages 页
+    fn release(&mut self, pages: usize) {
+        while self.frames.len() > pages {
+            dealloc_frame(self.frames.pop().unwrap());
+        }
+    }
+}
+
+// 把 [offset, offset + len) 按页分开，依次为 (页号, 页内偏移, 已经过的长度, 长度)
+fn page_chunks(offset: usize, len: usize) -> impl Iterator<Item = (usize, usize, usize, usize)> {
+    let mut done = 0;
+    core::iter::from_fn(move || {
+        if done == len {
+            return None;
+        }
+        let pos = offset + done;
+        let n = (PAGE_SIZE - pos % PAGE_SIZE).min(len - done);
+        done += n;
+        Some((pos / PAGE_SIZE, pos % PAGE_SIZE, done - n, n))
+    })
+}
+
+impl Drop for Content {
+    fn drop(&mut self) {
+        self.release(0);
+    }
+}
+
+struct RamINodeInner {
+    this: Weak<RamINode>,
+    parent: Weak<RamINode>,
+    children: BTreeMap<String, Arc<RamINode>>,
+    content: Content,
+    metadata: Metadata,
+    fs: Weak<RamFs>,
+}
+
+pub struct RamINode {
+    inner: RwLock<RamINodeInner>,
+}
+
+impl RamINode {
+    fn new(inode: usize, type_: FileType, mode: u16) -> Self {
+        RamINode {
+            inner: RwLock::new(RamINodeInner {
+                this: Weak::new(),
+                parent: Weak::new(),
+                children: BTreeMap::new(),
+                content: Content::new(),
+                metadata: Metadata {
+                    dev: 0,
+                    inode,
+                    size: 0,
+                    blk_size: BLOCK_SIZE,
+                    blocks: 0,
+                    atime: Timespec { sec: 0, nsec: 0 },
+                    mtime: Timespec { sec: 0, nsec: 0 },
+                    ctime: Timespec { sec: 0, nsec: 0 },
+                    type_,
+                    mode,
+                    nlinks: if type_ == FileType::Dir { 2 } else { 1 },
+                    uid: 0,
+                    gid: 0,
+                    rdev: 0,
+                },
+                fs: Weak::new(),
+            }),
+        }
+    }
+
+    fn type_(&self) -> FileType {
+        self.inner.read().metadata.type_
+    }
+
+    // 目录中名为 name 的项，不包括 . 与 ..
+    fn child(&self, name: &str) -> Result<Arc<RamINode>> {
+        let inner = self.inner.read();
+        if inner.metadata.type_ != FileType::Dir {
+            return Err(FsError::NotDir);
+        }
+        if name == "." || name == ".." {
+            return Err(FsError::InvalidParam);
+        }
+        inner
+            .children
+            .get(name)
+            .cloned()
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    // self 是否为 inode 本身或者位于其子树中；已被删除的目录不在任何子树中
+    fn is_descendant_of(&self, inode: &Arc<RamINode>) -> bool {
+        let mut current = match self.inner.read().this.upgrade() {
+            Some(current) => current,
+            None => return false,
+        };
+        loop {
+            if Arc::ptr_eq(&current, inode) {
+                return true;
+            }
+            let parent = match current.inner.read().parent.upgrade() {
+                Some(parent) => parent,
+                None => return false,
+            };
+            if Arc::ptr_eq(&parent, &current) {
+                return false;
+            }
+            current = parent;
+        }
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.len() > MAX_NAME_LEN {
+        return Err(FsError::InvalidParam);
+    }
+    Ok(())
+}
+
+impl RamINodeInner {
+    // 在持有写锁时检查，名字的检查与插入之间不会有别的线程创建同名的项；
+    // 已被删除的目录中不能再创建
+    fn check_insert(&self, name: &str) -> Result<()> {
+        if self.metadata.nlinks == 0 {
+            return Err(FsError::EntryNotFound);
+        }
+        if self.children.contains_key(name) {
+            return Err(FsError::EntryExist);
+        }
+        Ok(())
+    }
+}
+
+impl INode for RamINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let inner = self.inner.read();
+        if inner.metadata.type_ == FileType::Dir {
+            return Err(FsError::IsDir);
+        }
+        Ok(inner.content.read_at(offset, buf))
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        let mut inner = self.inner.write();
+        if inner.metadata.type_ == FileType::Dir {
+            return Err(FsError::IsDir);
+        }
+        inner.content.write_at(offset, buf)
+    }
+
+    fn poll(&self) -> Result<PollStatus> {
+        Ok(PollStatus {
+            read: true,
+            write: true,
+            error: false,
+        })
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        let inner = self.inner.read();
+        let mut metadata = inner.metadata.clone();
+        metadata.size = match metadata.type_ {
+            FileType::Dir => inner.children.len() + 2,
+            _ => inner.content.len,
+        };
+        metadata.blocks = (metadata.size + 511) / 512;
+        Ok(metadata)
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> Result<()> {
+        let mut inner = self.inner.write();
+        inner.metadata.atime = metadata.atime;
+        inner.metadata.mtime = metadata.mtime;
+        inner.metadata.ctime = metadata.ctime;
+        inner.metadata.mode = metadata.mode;
+        inner.metadata.uid = metadata.uid;
+        inner.metadata.gid = metadata.gid;
+        Ok(())
+    }
+
+    fn sync_all(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn sync_data(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn resize(&self, len: usize) -> Result<()> {
+        let mut inner = self.inner.write();
+        match inner.metadata.type_ {
+            FileType::File => inner.content.resize(len),
+            FileType::Dir => Err(FsError::IsDir),
+            _ => Err(FsError::NotFile),
+        }
+    }
+
+    fn create(&self, name: &str, type_: FileType, mode: u32) -> Result<Arc<dyn INode>> {
+        if self.type_() != FileType::Dir {
+            return Err(FsError::NotDir);
+        }
+        check_name(name)?;
+        let mut inner = self.inner.write();
+        inner.check_insert(name)?;
+        let fs = inner.fs.upgrade().ok_or(FsError::EntryNotFound)?;
+        let id = fs.next_inode.fetch_add(1, Ordering::Relaxed);
+        let inode = Arc::new(RamINode::new(id, type_, mode as u16));
+        {
+            let mut child = inode.inner.write();
+            child.this = Arc::downgrade(&inode);
+            child.parent = inner.this.clone();
+            child.fs = inner.fs.clone();
+        }
+        if type_ == FileType::Dir {
+            inner.metadata.nlinks += 1;
+        }
+        inner.children.insert(String::from(name), inode.clone());
+        Ok(inode)
+    }
+
+    fn link(&self, name: &str, other: &Arc<dyn INode>) -> Result<()> {
+        if self.type_() != FileType::Dir {
+            return Err(FsError::NotDir);
+        }
+        let other = other.downcast_ref::<RamINode>().ok_or(FsError::NotSameFs)?;
+        if !Weak::ptr_eq(&self.inner.read().fs, &other.inner.read().fs) {
+            return Err(FsError::NotSameFs);
+        }
+        if other.type_() == FileType::Dir {
+            return Err(FsError::IsDir);
+        }
+        check_name(name)?;
+        let other = other
+            .inner
+            .read()
+            .this
+            .upgrade()
+            .ok_or(FsError::EntryNotFound)?;
+        let mut inner = self.inner.write();
+        inner.check_insert(name)?;
+        other.inner.write().metadata.nlinks += 1;
+        inner.children.insert(String::from(name), other);
+        Ok(())
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        let inode = self.child(name)?;
+        let is_dir = inode.type_() == FileType::Dir;
+        if is_dir && !inode.inner.read().children.is_empty() {
+            return Err(FsError::DirNotEmpty);
+        }
+        let mut inner = self.inner.write();
+        inner.children.remove(name);
+        if is_dir {
+            inner.metadata.nlinks -= 1;
+            inode.inner.write().metadata.nlinks = 0;
+        } else {
+            inode.inner.write().metadata.nlinks -= 1;
+        }
+        Ok(())
+    }
+
+    fn move_(&self, old_name: &str, target: &Arc<dyn INode>, new_name: &str) -> Result<()> {
+        let target = target
+            .downcast_ref::<RamINode>()
+            .ok_or(FsError::NotSameFs)?;
+        if !Weak::ptr_eq(&self.inner.read().fs, &target.inner.read().fs) {
+            return Err(FsError::NotSameFs);
+        }
+        if target.type_() != FileType::Dir {
+            return Err(FsError::NotDir);
+        }
+        if target.inner.read().metadata.nlinks == 0 {
+            return Err(FsError::EntryNotFound);
+        }
+        let inode = self.child(old_name)?;
+        let is_dir = inode.type_() == FileType::Dir;
+        // 不能把目录移动到它自己的子树中
+        if is_dir && target.is_descendant_of(&inode) {
+            return Err(FsError::InvalidParam);
+        }
+        // 目标已存在时将其替换
+        match target.child(new_name) {
+            Ok(existing) => {
+                if Arc::ptr_eq(&existing, &inode) {
+                    return Ok(());
+                }
+                match (is_dir, existing.type_() == FileType::Dir) {
+                    (true, false) => return Err(FsError::NotDir),
+                    (false, true) => return Err(FsError::IsDir),
+                    _ => target.unlink(new_name)?,
+                }
+            }
+            Err(FsError::EntryNotFound) => check_name(new_name)?,
+            Err(e) => return Err(e),
+        }
+        {
+            let mut inner = self.inner.write();
+            inner.children.remove(old_name);
+            if is_dir {
+                inner.metadata.nlinks -= 1;
+            }
+        }
+        let mut target_inner = target.inner.write();
+        inode.inner.write().parent = target_inner.this.clone();
+        if is_dir {
+            target_inner.metadata.nlinks += 1;
+        }
+        target_inner.children.insert(String::from(new_name), inode);
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Result<Arc<dyn INode>> {
+        let inner = self.inner.read();
+        if inner.metadata.type_ != FileType::Dir {
+            return Err(FsError::NotDir);
+        }
+        match name {
+            // 当前目录或它的上级已被删除并释放
+            "." => Ok(inner.this.upgrade().ok_or(FsError::EntryNotFound)?),
+            ".." => Ok(inner.parent.upgrade().ok_or(FsError::EntryNotFound)?),
+            name => inner
+                .children
+                .get(name)
+                .map(|inode| inode.clone() as Arc<dyn INode>)
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+
+    fn get_entry(&self, id: usize) -> Result<String> {
+        let inner = self.inner.read();
+        if inner.metadata.type_ != FileType::Dir {
+            return Err(FsError::NotDir);
+        }
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            id => inner
+                .children
+                .keys()
+                .nth(id - 2)
+                .cloned()
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+
+    fn io_control(&self, _cmd: u32, _data: usize) -> Result<usize> {
+        Err(FsError::NotSupported)
+    }
+
+    // 文件系统被卸载并释放后，仍被打开或作为当前目录的 inode 属于一个空的文件系统
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        match self.inner.read().fs.upgrade() {
+            Some(fs) => fs,
+            None => RamFs::new(),
+        }
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,80 @@
+use rcore_fs::vfs::{FileType, Metadata};
+
+// 与 Linux riscv64 的 struct stat 布局相同
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stat {
+    pub dev: u64,
+    pub ino: u64,
+    pub mode: u32,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub rdev: u64,
+    __pad: u64,
+    pub size: i64,
+    pub blksize: i32,
+    __pad2: i32,
+    pub blocks: i64,
+    pub atime: i64,
+    pub atime_nsec: i64,
+    pub mtime: i64,
+    pub mtime_nsec: i64,
+    pub ctime: i64,
+    pub ctime_nsec: i64,
+    __unused: [u32; 2],
+}
+
+pub const S_IFIFO: u32 = 0o010000;
+pub const S_IFCHR: u32 = 0o020000;
+pub const S_IFDIR: u32 = 0o040000;
+pub const S_IFBLK: u32 = 0o060000;
+pub const S_IFREG: u32 = 0o100000;
+pub const S_IFLNK: u32 = 0o120000;
+pub const S_IFSOCK: u32 = 0o140000;
+
+impl From<Metadata> for Stat {
+    fn from(metadata: Metadata) -> Self {
+        let type_ = match metadata.type_ {
+            FileType::File => S_IFREG,
+            FileType::Dir => S_IFDIR,
+            FileType::SymLink => S_IFLNK,
+            FileType::CharDevice => S_IFCHR,
+            FileType::BlockDevice => S_IFBLK,
+            FileType::NamedPipe => S_IFIFO,
+            FileType::Socket => S_IFSOCK,
+        };
+        Stat {
+            dev: metadata.dev as u64,
+            ino: metadata.inode as u64,
+            mode: type_ | metadata.mode as u32,
+            nlink: metadata.nlinks as u32,
+            uid: metadata.uid as u32,
+            gid: metadata.gid as u32,
+            rdev: metadata.rdev as u64,
+            size: metadata.size as i64,
+            blksize: metadata.blk_size as i32,
+            blocks: metadata.blocks as i64,
+            atime: metadata.atime.sec,
+            atime_nsec: metadata.atime.nsec as i64,
+            mtime: metadata.mtime.sec,
+            mtime_nsec: metadata.mtime.nsec as i64,
+            ctime: metadata.ctime.sec,
+            ctime_nsec: metadata.ctime.nsec as i64,
+            ..Stat::default()
+        }
+    }
+}
+
+// getdents64 中 d_type 的取值
+pub fn dirent_type(type_: FileType) -> u8 {
+    match type_ {
+        FileType::NamedPipe => 1,
+        FileType::CharDevice => 2,
+        FileType::Dir => 4,
+        FileType::BlockDevice => 6,
+        FileType::File => 8,
+        FileType::SymLink => 10,
+        FileType::Socket => 12,
+    }
+}
@@ -0,0 +1,417 @@
+use super::devfs::DevINode;
+use crate::drivers::uart;
+use crate::interrupt::{disable_and_store, restore};
+use crate::memory::uaccess::{read_user, write_user};
+use crate::process::{self, signal::*, Tid};
+use crate::sync::condvar::Condvar;
+use crate::syscall::errno::{SysError, SysResult};
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use lazy_static::*;
+use rcore_fs::vfs::{FsError, INode, Result};
+use spin::Mutex;
+
+pub const TCGETS: usize = 0x5401;
+pub const TCSETS: usize = 0x5402;
+pub const TCSETSW: usize = 0x5403;
+pub const TCSETSF: usize = 0x5404;
+pub const TIOCGPGRP: usize = 0x540f;
+pub const TIOCSPGRP: usize = 0x5410;
+pub const TIOCGWINSZ: usize = 0x5413;
+
+// iflag
+const INLCR: u32 = 0o100;
+const IGNCR: u32 = 0o200;
+const ICRNL: u32 = 0o400;
+// oflag
+const OPOST: u32 = 0o1;
+const ONLCR: u32 = 0o4;
+// lflag
+const ISIG: u32 = 0o1;
+const ICANON: u32 = 0o2;
+const ECHO: u32 = 0o10;
+const ECHOE: u32 = 0o20;
+const ECHOK: u32 = 0o40;
+const ECHONL: u32 = 0o100;
+const ECHOCTL: u32 = 0o1000;
+const NOFLSH: u32 = 0o200;
+const ECHOKE: u32 = 0o4000;
+// cc 的下标
+const VINTR: usize = 0;
+const VQUIT: usize = 1;
+const VERASE: usize = 2;
+const VKILL: usize = 3;
+const VEOF: usize = 4;
+const VMIN: usize = 6;
+const VSUSP: usize = 10;
+const VWERASE: usize = 14;
+
+// 与 Linux 的 struct termios 布局相同
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Termios {
+    pub iflag: u32,
+    pub oflag: u32,
+    pub cflag: u32,
+    pub lflag: u32,
+    pub line: u8,
+    pub cc: [u8; 19],
+}
+
+// 与 Linux 新打开的终端相同：ICRNL | IXON、OPOST | ONLCR、B38400 | CS8 | CREAD | HUPCL，
+// ISIG | ICANON | ECHO | ECHOE | ECHOK | ECHOCTL | ECHOKE | IEXTEN
+impl Default for Termios {
+    fn default() -> Self {
+        Termios {
+            iflag: 0o2400,
+            oflag: 0o5,
+            cflag: 0o2277,
+            lflag: 0o105073,
+            line: 0,
+            // ^C ^\ DEL ^U ^D 0 1 0 ^Q ^S ^Z 0 ^R ^O ^W ^V 0 0 0
+            cc: [
+                3, 28, 127, 21, 4, 0, 1, 0, 17, 19, 26, 0, 18, 15, 23, 22, 0, 0, 0,
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WinSize {
+    pub row: u16,
+    pub col: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
+struct TtyInner {
+    termios: Termios,
+    // 规范模式下正在编辑的一行
+    line: Vec<u8>,
+    // 可以被读取的输入，None 表示 VEOF 分隔的位置
+    input: VecDeque<Option<u8>>,
+    // 前台进程组，0 表示还没有设置
+    pgrp: Tid,
+}
+
+// 控制台的行规程：处理输入字符的编辑与回显，以及输出时的换行转换
+pub struct Tty {
+    inner: Mutex<TtyInner>,
+    readable: Condvar,
+}
+
+lazy_static! {
+    pub static ref TTY: Tty = Tty {
+        inner: Mutex::new(TtyInner {
+            termios: Termios::default(),
+            line: Vec::new(),
+            input: VecDeque::new(),
+            pgrp: 0,
+        }),
+        readable: Condvar::new(),
+    };
+}
+
+// OPOST | ONLCR 时 \n 被转换为 \r\n
+fn process_output(termios: &Termios, buf: &[u8]) -> Vec<u8> {
+    let onlcr = termios.oflag & OPOST != 0 && termios.oflag & ONLCR != 0;
+    let mut out = Vec::with_capacity(buf.len());
+    for &ch in buf {
+        if ch == b'\n' && onlcr {
+            out.push(b'\r');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+impl TtyInner {
+    // 在中断处理中回显，只能使用轮询的输出
+    fn echo(&self, buf: &[u8]) {
+        for ch in process_output(&self.termios, buf) {
+            uart::putchar(ch);
+        }
+    }
+
+    fn echo_char(&self, ch: u8) {
+        let lflag = self.termios.lflag;
+        if lflag & ECHO == 0 {
+            if ch == b'\n' && lflag & ECHONL != 0 {
+                self.echo(b"\n");
+            }
+            return;
+        }
+        // ECHOCTL 时控制字符显示为 ^X
+        if lflag & ECHOCTL != 0 && ch < 0x20 && ch != b'\n' && ch != b'\t' {
+            self.echo(&[b'^', ch + 0x40]);
+        } else {
+            self.echo(&[ch]);
+        }
+    }
+
+    // 删除正在编辑的行的最后一个字符，返回是否删除成功
+    fn erase(&mut self) -> bool {
+        let ch = match self.line.pop() {
+            Some(ch) => ch,
+            None => return false,
+        };
+        let lflag = self.termios.lflag;
+        if lflag & ECHO != 0 && lflag & ECHOE != 0 {
+            let width = if lflag & ECHOCTL != 0 && ch < 0x20 && ch != b'\t' {
+                2
+            } else {
+                1
+            };
+            for _ in 0..width {
+                self.echo(b"\x08 \x08");
+            }
+        }
+        true
+    }
+
+    // 返回要发给前台进程组的信号
+    fn receive(&mut self, mut ch: u8) -> Option<usize> {
+        let iflag = self.termios.iflag;
+        if ch == b'\r' {
+            if iflag & IGNCR != 0 {
+                return None;
+            }
+            if iflag & ICRNL != 0 {
+                ch = b'\n';
+            }
+        } else if ch == b'\n' && iflag & INLCR != 0 {
+            ch = b'\r';
+        }
+
+        let lflag = self.termios.lflag;
+        let cc = self.termios.cc;
+        if lflag & ISIG != 0 {
+            let sig = match ch {
+                _ if ch == cc[VINTR] => Some(SIGINT),
+                _ if ch == cc[VQUIT] => Some(SIGQUIT),
+                _ if ch == cc[VSUSP] => Some(SIGTSTP),
+                _ => None,
+            };
+            if sig.is_some() {
+                if lflag & NOFLSH == 0 {
+                    self.line.clear();
+                    self.input.clear();
+                }
+                self.echo_char(ch);
+                return sig;
+            }
+        }
+        if lflag & ICANON == 0 {
+            self.echo_char(ch);
+            self.input.push_back(Some(ch));
+            return None;
+        }
+        if ch == cc[VERASE] {
+            self.erase();
+        } else if ch == cc[VWERASE] {
+            while self.line.last() == Some(&b' ') && self.erase() {}
+            while self.line.last().map_or(false, |&ch| ch != b' ') && self.erase() {}
+        } else if ch == cc[VKILL] {
+            if lflag & ECHOKE != 0 {
+                while self.erase() {}
+            } else {
+                self.line.clear();
+                self.echo_char(ch);
+                if lflag & ECHOK != 0 {
+                    self.echo(b"\n");
+                }
+            }
+        } else if ch == cc[VEOF] {
+            // 把已经输入的内容交给读者，行为空时读者读到文件结尾
+            self.input.extend(self.line.drain(..).map(Some));
+            self.input.push_back(None);
+        } else {
+            self.echo_char(ch);
+            self.line.push(ch);
+            if ch == b'\n' {
+                self.input.extend(self.line.drain(..).map(Some));
+            }
+        }
+        None
+    }
+
+    // 规范模式下最多读到一行的结尾，非规范模式下读取所有已到达的输入
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        let canonical = self.termios.lflag & ICANON != 0;
+        let mut len = 0;
+        while len < buf.len() {
+            match self.input.front() {
+                Some(Some(ch)) => {
+                    buf[len] = *ch;
+                    len += 1;
+                    self.input.pop_front();
+                    if canonical && buf[len - 1] == b'\n' {
+                        break;
+                    }
+                }
+                Some(None) => {
+                    self.input.pop_front();
+                    if canonical {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        len
+    }
+
+    fn readable(&self) -> bool {
+        !self.input.is_empty() || self.termios.lflag & ICANON == 0 && self.termios.cc[VMIN] == 0
+    }
+}
+
+impl Tty {
+    // 串口收到一个字符时调用
+    pub fn receive(&self, ch: u8) {
+        let flags = disable_and_store();
+        let mut inner = self.inner.lock();
+        let sig = inner.receive(ch);
+        let readable = inner.readable();
+        let pgrp = inner.pgrp;
+        drop(inner);
+        restore(flags);
+        if readable {
+            self.readable.notify_all();
+        }
+        if let (Some(sig), true) = (sig, pgrp != 0) {
+            process::send_signal_to_group(pgrp, sig);
+        }
+    }
+
+    // 规范模式下等到一行输入完成；非规范模式下 VMIN 为 0 时不等待，否则等到至少一个字节，
+    // VTIME 被忽略
+    // 后台进程组读取时收到 SIGTTIN，忽略了 SIGTTIN 时返回 EIO
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let flags = disable_and_store();
+            let mut inner = self.inner.lock();
+            let thread = process::current_thread_mut();
+            if inner.pgrp != 0 && inner.pgrp != thread.pgid {
+                drop(inner);
+                restore(flags);
+                if thread.ignored & bit(SIGTTIN) != 0 {
+                    return Err(FsError::DeviceError);
+                }
+                process::send_signal_to_group(thread.pgid, SIGTTIN);
+                return Err(FsError::Interrupted);
+            }
+            if inner.readable() {
+                let len = inner.read(buf);
+                drop(inner);
+                restore(flags);
+                return Ok(len);
+            }
+            drop(inner);
+            if process::signal_pending() {
+                restore(flags);
+                return Err(FsError::Interrupted);
+            }
+            // 从检查到加入等待队列期间保持关中断，不会错过接收中断的唤醒
+            self.readable.wait_restore(flags);
+        }
+    }
+
+    pub fn write(&self, buf: &[u8]) {
+        let out = {
+            let flags = disable_and_store();
+            let termios = self.inner.lock().termios;
+            restore(flags);
+            process_output(&termios, buf)
+        };
+        uart::write(&out);
+    }
+
+    pub fn readable(&self) -> bool {
+        let flags = disable_and_store();
+        let readable = self.inner.lock().readable();
+        restore(flags);
+        readable
+    }
+
+    fn termios(&self) -> Termios {
+        let flags = disable_and_store();
+        let termios = self.inner.lock().termios;
+        restore(flags);
+        termios
+    }
+
+    // flush 为真时丢弃还没有被读取的输入 (TCSETSF)
+    fn set_termios(&self, termios: Termios, flush: bool) {
+        let flags = disable_and_store();
+        let mut inner = self.inner.lock();
+        inner.termios = termios;
+        if flush {
+            inner.line.clear();
+            inner.input.clear();
+        }
+        // 切换到非规范模式时，正在编辑的内容立即可以被读取
+        if termios.lflag & ICANON == 0 {
+            let line: Vec<u8> = inner.line.drain(..).collect();
+            inner.input.extend(line.into_iter().map(Some));
+        }
+        drop(inner);
+        restore(flags);
+        self.readable.notify_all();
+    }
+
+    fn pgrp(&self) -> Tid {
+        let flags = disable_and_store();
+        let pgrp = self.inner.lock().pgrp;
+        restore(flags);
+        pgrp
+    }
+
+    fn set_pgrp(&self, pgrp: Tid) {
+        let flags = disable_and_store();
+        self.inner.lock().pgrp = pgrp;
+        restore(flags);
+    }
+}
+
+// 目前只有控制台是终端
+pub fn is_tty(inode: &Arc<dyn INode>) -> bool {
+    inode
+        .downcast_ref::<DevINode>()
+        .map_or(false, |inode| inode.is_console())
+}
+
+// 发送缓冲区由中断逐步清空，TCSETSW 不必等待输出完成
+// 只有一个终端，不区分控制终端属于哪个会话；前台进程组只能设为调用者会话中的进程组
+pub fn ioctl(cmd: usize, arg: usize) -> SysResult {
+    match cmd {
+        TCGETS => write_user(arg, &TTY.termios())?,
+        TCSETS | TCSETSW => TTY.set_termios(read_user(arg)?, false),
+        TCSETSF => TTY.set_termios(read_user(arg)?, true),
+        TIOCGPGRP => write_user(arg, &(TTY.pgrp() as i32))?,
+        TIOCSPGRP => {
+            let pgrp: i32 = read_user(arg)?;
+            if pgrp <= 0 {
+                return Err(SysError::EINVAL);
+            }
+            if !process::group_in_session(pgrp as Tid, process::current_thread_mut().sid) {
+                return Err(SysError::EPERM);
+            }
+            TTY.set_pgrp(pgrp as Tid);
+        }
+        TIOCGWINSZ => write_user(
+            arg,
+            &WinSize {
+                row: 24,
+                col: 80,
+                xpixel: 0,
+                ypixel: 0,
+            },
+        )?,
+        _ => return Err(SysError::ENOTTY),
+    }
+    Ok(0)
+}
@@ -1,11 +1,15 @@
 use crate::context::TrapFrame;
-use crate::memory::access_pa_via_va;
-use crate::process::tick;
-use crate::timer::{clock_set_next_event, TICKS};
+use crate::drivers::plic;
+use crate::memory::{kernel_stack, uaccess};
+use crate::process::{current_tid, handle_signals, kill_current, signal::SIGSEGV, tick};
+use crate::syscall::errno::SysError;
+use crate::timer::{clock_set_next_event, wake_sleepers, TICKS};
 use riscv::register::sie;
 use riscv::register::{
     scause::{self, Exception, Interrupt, Trap},
-    sepc, sscratch, sstatus, stvec,
+    sepc, sscratch,
+    sstatus::{self, SPP},
+    stvec,
 };
 
 global_asm!(include_str!("trap/trap.asm"));
@@ -25,26 +29,16 @@ pub fn init() {
 
         // closed by OpenSBI, so we open them manually
         // see https://github.com/rcore-os/rCore/blob/54fddfbe1d402ac1fafd9d58a0bd4f6a8dd99ece/kernel/src/arch/riscv32/board/virt/mod.rs#L4
-        init_external_interrupt();
-        enable_serial_interrupt();
+        plic::init();
     }
     println!("++++ setup interrupt! ++++");
 }
 
-pub unsafe fn init_external_interrupt() {
-    let HART0_S_MODE_INTERRUPT_ENABLES: *mut u32 = access_pa_via_va(0x0c00_2080) as *mut u32;
-    const SERIAL: u32 = 0xa;
-    HART0_S_MODE_INTERRUPT_ENABLES.write_volatile(1 << SERIAL);
-}
-
-pub unsafe fn enable_serial_interrupt() {
-    let UART16550: *mut u8 = access_pa_via_va(0x10000000) as *mut u8;
-    UART16550.add(4).write_volatile(0x0B);
-    UART16550.add(1).write_volatile(0x01);
-}
-
 #[no_mangle]
 pub fn rust_trap(tf: &mut TrapFrame) {
+    if kernel_stack::on_overflow_stack(tf as *const TrapFrame as usize) {
+        kernel_stack_overflow(tf);
+    }
     match tf.scause.cause() {
         Trap::Exception(Exception::Breakpoint) => breakpoint(&mut tf.sepc),
         Trap::Interrupt(Interrupt::SupervisorTimer) => super_timer(),
@@ -55,6 +49,10 @@ pub fn rust_trap(tf: &mut TrapFrame) {
         Trap::Interrupt(Interrupt::SupervisorExternal) => external(),
         _ => panic!("undefined trap!"),
     }
+    // 返回用户态前处理信号
+    if let SPP::User = tf.sstatus.spp() {
+        handle_signals();
+    }
 }
 
 fn breakpoint(sepc: &mut usize) {
@@ -64,40 +62,57 @@ fn breakpoint(sepc: &mut usize) {
 
 fn super_timer() {
     clock_set_next_event();
+    unsafe {
+        TICKS += 1;
+    }
+    wake_sleepers();
     tick();
 }
 fn page_fault(tf: &mut TrapFrame) {
+    if kernel_stack::is_guard(tf.stval) {
+        kernel_stack_overflow(tf);
+    }
+    // 复制用户内存时访问了非法地址，让复制函数返回 EFAULT
+    if let Some(fixup) = uaccess::fault_fixup(tf.sepc) {
+        tf.sepc = fixup;
+        return;
+    }
     println!(
         "{:?} va = {:#x} instruction = {:#x}",
         tf.scause.cause(),
         tf.stval,
         tf.sepc
     );
+    // 用户程序访问了非法地址，只终止它自己
+    if let SPP::User = tf.sstatus.spp() {
+        kill_current(SIGSEGV);
+    }
     panic!("page fault!");
 }
 
+fn kernel_stack_overflow(tf: &TrapFrame) -> ! {
+    println!(
+        "sp = {:#x} va = {:#x} instruction = {:#x}",
+        tf.x[2], tf.stval, tf.sepc
+    );
+    panic!("kernel stack overflow in tid {}", current_tid());
+}
+
 fn syscall(tf: &mut TrapFrame) {
     tf.sepc += 4;
-    let ret = crate::syscall::syscall(tf.x[17], [tf.x[10], tf.x[11], tf.x[12]], tf);
+    let args = [tf.x[10], tf.x[11], tf.x[12], tf.x[13], tf.x[14], tf.x[15]];
+    let ret = crate::syscall::syscall(tf.x[17], args, tf);
     tf.x[10] = ret as usize;
+    // 没有信号处理函数，打断系统调用的信号只会让线程暂停，继续后重新执行这个系统调用
+    if ret == -(SysError::EINTR as isize) {
+        handle_signals();
+        tf.sepc -= 4;
+        tf.x[10] = args[0];
+    }
 }
 
 fn external() {
-    let _ = try_serial();
-}
-
-fn try_serial() -> bool {
-    match super::io::getchar_option() {
-        Some(ch) => {
-            if (ch == '\r') {
-                crate::fs::stdio::STDIN.push('\n');
-            } else {
-                crate::fs::stdio::STDIN.push(ch);
-            }
-            true
-        }
-        None => false,
-    }
+    plic::handle_interrupt();
 }
 
 #[inline(always)]
@@ -1,13 +1,13 @@
-use crate::sbi;
+use crate::drivers::uart;
 use core::fmt::{self, Write};
 
 pub fn putchar(ch: char) {
-    sbi::console_putchar(ch as u8 as usize);
+    uart::putchar(ch as u8);
 }
 
 pub fn puts(s: &str) {
-    for ch in s.chars() {
-        putchar(ch);
+    for ch in s.bytes() {
+        uart::putchar(ch);
     }
 }
 
@@ -36,20 +36,3 @@ macro_rules! println {
     () => ($crate::print!("\n"));
     ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
 }
-
-pub fn getchar() -> char {
-    let c = sbi::console_getchar() as u8;
-
-    match c {
-        255 => '\0',
-        c => c as char,
-    }
-}
-// 调用 OpenSBI 接口
-pub fn getchar_option() -> Option<char> {
-    let c = sbi::console_getchar() as isize;
-    match c {
-        -1 => None,
-        c => Some(c as u8 as char),
-    }
-}
@@ -2,6 +2,8 @@ use core::panic::PanicInfo;
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
+    crate::interrupt::disable_and_store();
+    crate::drivers::uart::enter_panic();
     println!("{}", info);
     loop {}
 }
@@ -6,16 +6,22 @@
 #![feature(const_in_array_repeat_expressions)]
 
 extern crate alloc;
+#[macro_use]
+extern crate log;
 
 #[macro_use]
 mod io;
 
+mod boot;
+mod cmdline;
 mod consts;
 mod context;
+mod drivers;
 mod fs;
 mod init;
 mod interrupt;
 mod lang_items;
+mod logging;
 mod memory;
 mod process;
 mod sbi;
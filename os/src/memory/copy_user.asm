@@ -0,0 +1,24 @@
+# 在内核与用户地址空间之间复制 a2 个字节：a0 = dst, a1 = src
+# 成功返回 0；访问时发生缺页则由 page_fault 跳转到 __copy_user_fault，返回 1
+	.section .text
+	.globl __copy_user
+__copy_user:
+	beqz a2, 2f
+1:
+	lbu t0, 0(a1)
+	sb t0, 0(a0)
+	addi a0, a0, 1
+	addi a1, a1, 1
+	addi a2, a2, -1
+	bnez a2, 1b
+2:
+	li a0, 0
+	ret
+
+	.globl __copy_user_fault
+__copy_user_fault:
+	li a0, 1
+	ret
+
+	.globl __copy_user_end
+__copy_user_end:
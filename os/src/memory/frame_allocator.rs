@@ -27,10 +27,10 @@ impl SegmentTreeAllocator {
         }
     }
 
-    pub fn alloc(&mut self) -> usize {
-        // assume that we never run out of physical memory
+    // 没有空闲页帧时返回 None
+    pub fn alloc(&mut self) -> Option<usize> {
         if self.a[1] == 1 {
-            panic!("physical memory depleted!");
+            return None;
         }
         let mut p = 1;
         while p < self.m {
@@ -47,7 +47,26 @@ impl SegmentTreeAllocator {
             self.a[p] = self.a[p << 1] & self.a[(p << 1) | 1];
             p >>= 1;
         }
-        result
+        Some(result)
+    }
+
+    // 将 [l, r) 中的物理页帧标记为已占用
+    pub fn reserve(&mut self, l: usize, r: usize) {
+        for n in l.max(self.offset + 1)..r.min(self.offset + self.n) {
+            let mut p = n + self.m - self.offset;
+            self.a[p] = 1;
+            p >>= 1;
+            while p > 0 {
+                self.a[p] = self.a[p << 1] & self.a[(p << 1) | 1];
+                p >>= 1;
+            }
+        }
+    }
+
+    // (可分配的页帧总数, 空闲页帧数)
+    pub fn stats(&self) -> (usize, usize) {
+        let free = (1..self.n).filter(|&i| self.a[self.m + i] == 0).count();
+        (self.n - 1, free)
     }
 
     pub fn dealloc(&mut self, n: usize) {
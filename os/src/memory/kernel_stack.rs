@@ -0,0 +1,115 @@
+use crate::consts::*;
+use crate::memory::paging::PageTableImpl;
+use crate::memory::{access_pa_via_va, alloc_frame, dealloc_frame};
+use riscv::addr::Frame;
+use riscv::asm::sfence_vma;
+use riscv::paging::{PageTable, PageTableFlags as EF};
+use spin::Mutex;
+
+// 内核栈区域的二级页表由所有地址空间共享，
+// 因此无论当前使用哪个页表，映射的内核栈都是可见的
+struct KernelStackRegion {
+    table: Option<Frame>,
+    used: [bool; KERNEL_STACK_SLOTS],
+}
+
+static REGION: Mutex<KernelStackRegion> = Mutex::new(KernelStackRegion {
+    table: None,
+    used: [false; KERNEL_STACK_SLOTS],
+});
+
+fn table_of(frame: Frame) -> &'static mut PageTable {
+    unsafe { &mut *(access_pa_via_va(frame.start_address().as_usize()) as *mut PageTable) }
+}
+
+fn new_table() -> Frame {
+    let frame = alloc_frame().expect("alloc_frame failed!");
+    table_of(frame).zero();
+    frame
+}
+
+pub fn init() {
+    REGION.lock().table = Some(new_table());
+}
+
+pub fn share_with(pt: &mut PageTableImpl) {
+    let table = REGION
+        .lock()
+        .table
+        .expect("kernel stack region is not initialized!");
+    pt.set_root_entry(KERNEL_STACK_REGION, table);
+}
+
+impl KernelStackRegion {
+    fn map(&mut self, va: usize, frame: Frame) {
+        let l1 = table_of(self.table.unwrap());
+        let entry = &mut l1[(va >> 21) & 0x1ff];
+        if entry.is_unused() {
+            entry.set(new_table(), EF::VALID);
+        }
+        let l0 = table_of(entry.frame());
+        l0[(va >> 12) & 0x1ff].set(frame, EF::VALID | EF::READABLE | EF::WRITABLE);
+        unsafe {
+            sfence_vma(0, va);
+        }
+    }
+
+    fn unmap(&mut self, va: usize) -> Frame {
+        let l1 = table_of(self.table.unwrap());
+        let l0 = table_of(l1[(va >> 21) & 0x1ff].frame());
+        let entry = &mut l0[(va >> 12) & 0x1ff];
+        let frame = entry.frame();
+        entry.set_unused();
+        unsafe {
+            sfence_vma(0, va);
+        }
+        frame
+    }
+}
+
+fn slot_bottom(slot: usize) -> usize {
+    KERNEL_STACK_REGION
+        + slot * KERNEL_STACK_SLOT_SIZE
+        + (KERNEL_STACK_SLOT_SIZE - KERNEL_STACK_SIZE)
+}
+
+// 分配一个内核栈，返回栈底地址
+pub fn alloc() -> usize {
+    let mut region = REGION.lock();
+    let slot = region
+        .used
+        .iter()
+        .position(|used| !used)
+        .expect("kernel stack slots depleted!");
+    region.used[slot] = true;
+    let bottom = slot_bottom(slot);
+    for va in (bottom..bottom + KERNEL_STACK_SIZE).step_by(PAGE_SIZE) {
+        region.map(va, alloc_frame().expect("alloc_frame failed!"));
+    }
+    bottom
+}
+
+pub fn dealloc(bottom: usize) {
+    let mut region = REGION.lock();
+    for va in (bottom..bottom + KERNEL_STACK_SIZE).step_by(PAGE_SIZE) {
+        dealloc_frame(region.unmap(va));
+    }
+    region.used[(bottom - KERNEL_STACK_REGION) / KERNEL_STACK_SLOT_SIZE] = false;
+}
+
+// 地址是否落在某个内核栈下方的保护区域中
+pub fn is_guard(va: usize) -> bool {
+    va >= KERNEL_STACK_REGION
+        && va < KERNEL_STACK_REGION + KERNEL_STACK_REGION_SIZE
+        && (va - KERNEL_STACK_REGION) % KERNEL_STACK_SLOT_SIZE
+            < KERNEL_STACK_SLOT_SIZE - KERNEL_STACK_SIZE
+}
+
+// trap.asm 发现内核栈溢出时会切换到这个专用栈上
+pub fn on_overflow_stack(sp: usize) -> bool {
+    extern "C" {
+        fn kstack_overflow();
+        fn kstack_overflow_top();
+    }
+    sp >= kstack_overflow as usize && sp < kstack_overflow_top as usize
+}
@@ -1,6 +1,7 @@
 use super::{attr::MemoryAttr, handler::MemoryHandler};
 use crate::consts::PAGE_SIZE;
 use crate::memory::paging::{PageRange, PageTableImpl};
+use crate::syscall::errno::SysError;
 use alloc::boxed::Box;
 
 #[derive(Debug, Clone)]
@@ -12,10 +13,17 @@ pub struct MemoryArea {
 }
 
 impl MemoryArea {
-    pub fn map(&self, pt: &mut PageTableImpl) {
+    // 中途失败时撤销已建立的映射
+    pub fn map(&self, pt: &mut PageTableImpl) -> Result<(), SysError> {
         for page in PageRange::new(self.start, self.end) {
-            self.handler.map(pt, page, &self.attr);
+            if let Err(e) = self.handler.map(pt, page, &self.attr) {
+                for mapped in PageRange::new(self.start, page) {
+                    self.handler.unmap(pt, mapped);
+                }
+                return Err(e);
+            }
         }
+        Ok(())
     }
     fn unmap(&self, pt: &mut PageTableImpl) {
         for page in PageRange::new(self.start, self.end) {
@@ -23,6 +31,18 @@ impl MemoryArea {
         }
     }
 
+    pub fn range(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    pub fn handler(&self) -> &dyn MemoryHandler {
+        &*self.handler
+    }
+
+    pub fn attr(&self) -> &MemoryAttr {
+        &self.attr
+    }
+
     pub fn is_overlap_with(&self, start_addr: usize, end_addr: usize) -> bool {
         let p1 = self.start / PAGE_SIZE;
         let p2 = (self.end - 1) / PAGE_SIZE + 1;
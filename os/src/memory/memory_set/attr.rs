@@ -1,4 +1,5 @@
 use crate::memory::paging::PageEntry;
+use core::fmt;
 
 #[derive(Clone, Debug)]
 pub struct MemoryAttr {
@@ -29,6 +30,13 @@ impl MemoryAttr {
         self
     }
 
+    pub fn is_user(&self) -> bool {
+        self.user
+    }
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
     pub fn apply(&self, entry: &mut PageEntry) {
         entry.set_present(true);
         entry.set_user(self.user);
@@ -36,3 +44,16 @@ impl MemoryAttr {
         entry.set_execute(self.execute);
     }
 }
+
+// 形如 rwxu 的权限描述
+impl fmt::Display for MemoryAttr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "r{}{}{}",
+            if self.readonly { '-' } else { 'w' },
+            if self.execute { 'x' } else { '-' },
+            if self.user { 'u' } else { '-' }
+        )
+    }
+}
@@ -1,14 +1,16 @@
 use super::attr::MemoryAttr;
 use crate::consts::PAGE_SIZE;
 use crate::memory::access_pa_via_va;
-use crate::memory::alloc_frame;
 use crate::memory::paging::PageTableImpl;
+use crate::memory::{alloc_frame, dealloc_frame};
+use crate::syscall::errno::SysError;
 use alloc::boxed::Box;
 use core::fmt::Debug;
 
-pub trait MemoryHandler: Debug + 'static {
+pub trait MemoryHandler: Debug + Send + Sync + 'static {
     fn box_clone(&self) -> Box<dyn MemoryHandler>;
-    fn map(&self, pt: &mut PageTableImpl, va: usize, attr: &MemoryAttr);
+    // 物理页帧不足时返回 ENOMEM，va 保持未映射
+    fn map(&self, pt: &mut PageTableImpl, va: usize, attr: &MemoryAttr) -> Result<(), SysError>;
     fn unmap(&self, pt: &mut PageTableImpl, va: usize);
     fn page_copy(&self, pt: &mut PageTableImpl, va: usize, src: usize, length: usize);
 }
@@ -33,8 +35,9 @@ impl MemoryHandler for Linear {
     fn box_clone(&self) -> Box<dyn MemoryHandler> {
         Box::new(self.clone())
     }
-    fn map(&self, pt: &mut PageTableImpl, va: usize, attr: &MemoryAttr) {
-        attr.apply(pt.map(va, va - self.offset));
+    fn map(&self, pt: &mut PageTableImpl, va: usize, attr: &MemoryAttr) -> Result<(), SysError> {
+        attr.apply(pt.try_map(va, va - self.offset).ok_or(SysError::ENOMEM)?);
+        Ok(())
     }
     fn unmap(&self, pt: &mut PageTableImpl, va: usize) {
         pt.unmap(va);
@@ -70,14 +73,29 @@ impl MemoryHandler for ByFrame {
         Box::new(self.clone())
     }
 
-    fn map(&self, pt: &mut PageTableImpl, va: usize, attr: &MemoryAttr) {
-        let frame = alloc_frame().expect("alloc_frame failed!");
+    fn map(&self, pt: &mut PageTableImpl, va: usize, attr: &MemoryAttr) -> Result<(), SysError> {
+        let frame = alloc_frame().ok_or(SysError::ENOMEM)?;
         let pa = frame.start_address().as_usize();
-        attr.apply(pt.map(va, pa));
+        // 用户程序的 .bss、堆与匿名映射都要求初始内容为 0
+        unsafe {
+            core::ptr::write_bytes(access_pa_via_va(pa) as *mut u8, 0, PAGE_SIZE);
+        }
+        match pt.try_map(va, pa) {
+            Some(entry) => {
+                attr.apply(entry);
+                Ok(())
+            }
+            None => {
+                dealloc_frame(frame);
+                Err(SysError::ENOMEM)
+            }
+        }
     }
 
     fn unmap(&self, pt: &mut PageTableImpl, va: usize) {
+        let frame = pt.get_entry(va).expect("get pa error!").0.frame();
         pt.unmap(va);
+        dealloc_frame(frame);
     }
     fn page_copy(&self, pt: &mut PageTableImpl, va: usize, src: usize, length: usize) {
         let pa = pt.get_entry(va).expect("get pa error!").0.addr().as_usize();
@@ -3,12 +3,13 @@ pub mod attr;
 pub mod handler;
 
 use crate::consts::*;
-use crate::memory::access_pa_via_va;
-use crate::memory::paging::PageTableImpl;
+use crate::memory::paging::{PageRange, PageTableImpl};
+use crate::memory::{access_pa_via_va, kernel_stack, mmio_regions, physical_memory_end};
+use crate::syscall::errno::SysError;
 use alloc::{boxed::Box, vec::Vec};
 use area::MemoryArea;
 use attr::MemoryAttr;
-use handler::{Linear, MemoryHandler};
+use handler::{ByFrame, Linear, MemoryHandler};
 
 pub struct MemorySet {
     areas: Vec<MemoryArea>,
@@ -24,16 +25,29 @@ impl MemorySet {
         handler: impl MemoryHandler,
         data: Option<(usize, usize)>,
     ) {
+        self.try_push(start, end, attr, handler, data)
+            .expect("out of physical memory!");
+    }
+    // 物理页帧不足时返回 ENOMEM，不会留下部分映射的区域
+    pub fn try_push(
+        &mut self,
+        start: usize,
+        end: usize,
+        attr: MemoryAttr,
+        handler: impl MemoryHandler,
+        data: Option<(usize, usize)>,
+    ) -> Result<(), SysError> {
         assert!(start <= end, "invalid memory area!");
         assert!(self.test_free_area(start, end), "memory area overlap!");
         let area = MemoryArea::new(start, end, Box::new(handler), attr);
-        area.map(&mut self.page_table);
+        area.map(&mut self.page_table)?;
         if let Some((src, length)) = data {
             area.page_copy(&mut self.page_table, src, length);
         }
         self.areas.push(area);
+        Ok(())
     }
-    fn test_free_area(&self, start: usize, end: usize) -> bool {
+    pub fn test_free_area(&self, start: usize, end: usize) -> bool {
         self.areas
             .iter()
             .find(|area| area.is_overlap_with(start, end))
@@ -99,13 +113,156 @@ impl MemorySet {
         // 物理内存 R|W
         self.push(
             (end as usize / PAGE_SIZE + 1) * PAGE_SIZE,
-            access_pa_via_va(PHYSICAL_MEMORY_END),
+            access_pa_via_va(physical_memory_end()),
             MemoryAttr::new(),
             Linear::new(offset),
             None,
         );
+        // 设备寄存器 R|W
+        for (start, end) in mmio_regions() {
+            self.push_mmio(start, end);
+        }
+        // 内核栈区域，所有页表共享
+        kernel_stack::share_with(&mut self.page_table);
+    }
+    pub fn push_mmio(&mut self, start: usize, end: usize) {
+        self.push(
+            access_pa_via_va(start),
+            access_pa_via_va(end),
+            MemoryAttr::new(),
+            Linear::new(PHYSICAL_MEMORY_OFFSET),
+            None,
+        );
+    }
+    // [start, end) 涉及的页是否都属于用户可以访问的区域，write 时还要求可写
+    pub fn check_user_range(&self, start: usize, end: usize, write: bool) -> bool {
+        let mut addr = start / PAGE_SIZE * PAGE_SIZE;
+        while addr < end {
+            let area = self.areas.iter().find(|area| {
+                area.is_overlap_with(addr, addr + 1)
+                    && area.attr().is_user()
+                    && (!write || !area.attr().is_readonly())
+            });
+            match area {
+                Some(area) => addr = (area.range().1 - 1) / PAGE_SIZE * PAGE_SIZE + PAGE_SIZE,
+                None => return false,
+            }
+        }
+        true
+    }
+    // 解除 [start, end) 中用户区域的映射，与之部分重叠的区域被拆开，内核的区域保持不变
+    pub fn remove(&mut self, start: usize, end: usize) {
+        let areas = core::mem::replace(&mut self.areas, Vec::new());
+        for area in areas {
+            if !area.attr().is_user() || !area.is_overlap_with(start, end) {
+                self.areas.push(area);
+                continue;
+            }
+            let (l, r) = area.range();
+            for page in PageRange::new(l.max(start), r.min(end)) {
+                area.handler().unmap(&mut self.page_table, page);
+            }
+            if l < start {
+                let handler = area.handler().box_clone();
+                self.areas
+                    .push(MemoryArea::new(l, start, handler, area.attr().clone()));
+            }
+            if r > end {
+                let handler = area.handler().box_clone();
+                self.areas
+                    .push(MemoryArea::new(end, r, handler, area.attr().clone()));
+            }
+        }
+    }
+    // 修改 [start, end) 中用户区域的权限，与之部分重叠的区域被拆开
+    pub fn protect(&mut self, start: usize, end: usize, attr: MemoryAttr) {
+        let areas = core::mem::replace(&mut self.areas, Vec::new());
+        for area in areas {
+            if !area.attr().is_user() || !area.is_overlap_with(start, end) {
+                self.areas.push(area);
+                continue;
+            }
+            let (l, r) = area.range();
+            let (l_in, r_in) = (l.max(start), r.min(end));
+            for page in PageRange::new(l_in, r_in) {
+                let entry = self.page_table.get_entry(page).expect("get pa error!");
+                attr.apply(entry);
+                entry.update();
+            }
+            if l < start {
+                let handler = area.handler().box_clone();
+                self.areas
+                    .push(MemoryArea::new(l, start, handler, area.attr().clone()));
+            }
+            let handler = area.handler().box_clone();
+            self.areas
+                .push(MemoryArea::new(l_in, r_in, handler, attr.clone()));
+            if r > end {
+                let handler = area.handler().box_clone();
+                self.areas
+                    .push(MemoryArea::new(end, r, handler, area.attr().clone()));
+            }
+        }
+    }
+    // fork 时复制用户区域及其内容，用户区域都由 ByFrame 映射
+    pub fn try_clone(&mut self) -> Result<MemorySet, SysError> {
+        let mut memory_set = MemorySet::new();
+        for area in self.areas.iter().filter(|area| area.attr().is_user()) {
+            let (start, end) = area.range();
+            memory_set.try_push(start, end, area.attr().clone(), ByFrame::new(), None)?;
+            for page in PageRange::new(start, end) {
+                let pa = self
+                    .page_table
+                    .get_entry(page)
+                    .expect("get pa error!")
+                    .0
+                    .addr()
+                    .as_usize();
+                memory_set.write(page, unsafe {
+                    core::slice::from_raw_parts(access_pa_via_va(pa) as *const u8, PAGE_SIZE)
+                });
+            }
+        }
+        Ok(memory_set)
+    }
+    // 从 from 开始向上找到第一段长为 len 的空闲区域
+    pub fn find_free_area(&self, from: usize, len: usize) -> usize {
+        let mut start = from;
+        while let Some(area) = self
+            .areas
+            .iter()
+            .find(|area| area.is_overlap_with(start, start + len))
+        {
+            start = (area.range().1 - 1) / PAGE_SIZE * PAGE_SIZE + PAGE_SIZE;
+        }
+        start
+    }
+    // 通过物理内存的线性映射写入，这个地址空间不必是当前的
+    pub fn write(&mut self, va: usize, data: &[u8]) {
+        let mut written = 0;
+        while written < data.len() {
+            let addr = va + written;
+            let len = (PAGE_SIZE - addr % PAGE_SIZE).min(data.len() - written);
+            let entry = self.page_table.get_entry(addr).expect("get pa error!");
+            let pa = entry.0.addr().as_usize() + addr % PAGE_SIZE;
+            unsafe {
+                core::slice::from_raw_parts_mut(access_pa_via_va(pa) as *mut u8, len)
+                    .copy_from_slice(&data[written..written + len]);
+            }
+            written += len;
+        }
+    }
+    pub fn areas(&self) -> &[MemoryArea] {
+        &self.areas
     }
     pub fn token(&self) -> usize {
         self.page_table.token()
     }
 }
+
+// 释放用户区域占用的页帧，内核区域是线性映射，不占用页帧
+impl Drop for MemorySet {
+    fn drop(&mut self) {
+        self.remove(0, USER_END);
+    }
+}
@@ -1,33 +1,57 @@
 mod frame_allocator;
+pub mod kernel_stack;
 pub mod memory_set;
 pub mod paging;
+pub mod uaccess;
 
 use crate::consts::*;
+use alloc::vec::Vec;
 use buddy_system_allocator::LockedHeap;
 use frame_allocator::SEGMENT_TREE_ALLOCATOR as FRAME_ALLOCATOR;
 use memory_set::{attr::MemoryAttr, handler::Linear, MemorySet};
 use riscv::addr::{Frame, Page, PhysAddr, VirtAddr};
-use riscv::register::sstatus;
+use spin::Mutex;
 
+static mut PHYSICAL_MEMORY_END: usize = 0;
+
+// 需要映射到每个地址空间中的设备寄存器区域 (物理地址)
+static MMIO_REGIONS: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+// 内核线程使用的地址空间
+static KERNEL_MEMORY_SET: Mutex<Option<MemorySet>> = Mutex::new(None);
+
+// 可分配的物理页帧为 [l, r)
 pub fn init(l: usize, r: usize) {
+    // SUM 保持关闭，内核只通过 uaccess 中的函数访问用户内存
     unsafe {
-        sstatus::set_sum();
+        PHYSICAL_MEMORY_END = r << 12;
     }
     FRAME_ALLOCATOR.lock().init(l, r);
-    init_heap();
+    // initrd 所在的物理页帧不能被分配出去
+    if let Some((start, end)) = crate::drivers::board().initrd {
+        FRAME_ALLOCATOR
+            .lock()
+            .reserve(start / PAGE_SIZE, (end - 1) / PAGE_SIZE + 1);
+    }
+    kernel_stack::init();
     kernel_remap();
     println!("++++ setup memory!    ++++");
 }
 
+pub fn physical_memory_end() -> usize {
+    unsafe { PHYSICAL_MEMORY_END }
+}
+
 pub fn alloc_frame() -> Option<Frame> {
-    Some(Frame::of_ppn(FRAME_ALLOCATOR.lock().alloc()))
+    FRAME_ALLOCATOR.lock().alloc().map(Frame::of_ppn)
 }
 
 pub fn dealloc_frame(f: Frame) {
     FRAME_ALLOCATOR.lock().dealloc(f.number())
 }
 
-fn init_heap() {
+// 设备树需要在分配物理页帧之前解析，因此堆要最先初始化
+pub fn init_heap() {
     static mut HEAP: [u8; KERNEL_HEAP_SIZE] = [0; KERNEL_HEAP_SIZE];
     unsafe {
         DYNAMIC_ALLOCATOR
@@ -36,6 +60,17 @@ fn init_heap() {
     }
 }
 
+// (页帧总数, 空闲页帧数)
+pub fn frame_stats() -> (usize, usize) {
+    FRAME_ALLOCATOR.lock().stats()
+}
+
+// (堆总大小, 已分配字节数)
+pub fn heap_stats() -> (usize, usize) {
+    let heap = DYNAMIC_ALLOCATOR.lock();
+    (heap.stats_total_bytes(), heap.stats_alloc_actual())
+}
+
 pub fn access_pa_via_va(pa: usize) -> usize {
     pa + PHYSICAL_MEMORY_OFFSET
 }
@@ -54,24 +89,31 @@ pub fn kernel_remap() {
         Linear::new(PHYSICAL_MEMORY_OFFSET),
         None,
     );
-    memory_set.push(
-        access_pa_via_va(0x0c00_2000),
-        access_pa_via_va(0x0c00_3000),
-        MemoryAttr::new(),
-        Linear::new(PHYSICAL_MEMORY_OFFSET),
-        None,
-    );
-    memory_set.push(
-        access_pa_via_va(0x1000_0000),
-        access_pa_via_va(0x1000_1000),
-        MemoryAttr::new(),
-        Linear::new(PHYSICAL_MEMORY_OFFSET),
-        None,
-    );
 
     unsafe {
         memory_set.activate();
     }
+    *KERNEL_MEMORY_SET.lock() = Some(memory_set);
+}
+
+// 映射物理地址 [start, end) 处的设备寄存器，返回其虚拟地址
+pub fn map_mmio(start: usize, end: usize) -> usize {
+    let region = (
+        start / PAGE_SIZE * PAGE_SIZE,
+        (end - 1) / PAGE_SIZE * PAGE_SIZE + PAGE_SIZE,
+    );
+    let mut regions = MMIO_REGIONS.lock();
+    if !regions.contains(&region) {
+        regions.push(region);
+        if let Some(memory_set) = KERNEL_MEMORY_SET.lock().as_mut() {
+            memory_set.push_mmio(region.0, region.1);
+        }
+    }
+    access_pa_via_va(start)
+}
+
+pub fn mmio_regions() -> Vec<(usize, usize)> {
+    MMIO_REGIONS.lock().clone()
 }
 
 #[global_allocator]
@@ -104,14 +104,19 @@ impl PageTableImpl {
     }
 
     pub fn map(&mut self, va: usize, pa: usize) -> &mut PageEntry {
+        self.try_map(va, pa).expect("fail to map a page!")
+    }
+
+    // 没有页帧用于中间一级的页表时返回 None
+    pub fn try_map(&mut self, va: usize, pa: usize) -> Option<&mut PageEntry> {
         let flags = EF::VALID | EF::READABLE | EF::WRITABLE;
         let page = Page::of_addr(VirtAddr::new(va));
         let frame = Frame::of_addr(PhysAddr::new(pa));
         self.page_table
             .map_to(page, frame, flags, &mut FrameAllocatorForPaging)
-            .unwrap()
+            .ok()?
             .flush();
-        self.get_entry(va).expect("fail to get an entry!")
+        self.get_entry(va)
     }
 
     pub fn unmap(&mut self, va: usize) {
@@ -134,6 +139,15 @@ impl PageTableImpl {
         self.root_frame.number() | (8 << 60)
     }
 
+    // 将根页表中 va 所在的 1 GiB 表项指向给定的下一级页表
+    pub fn set_root_entry(&mut self, va: usize, table: Frame) {
+        let root = unsafe {
+            &mut *(access_pa_via_va(self.root_frame.start_address().as_usize())
+                as *mut PageTableEntryArray)
+        };
+        root[(va >> 30) & 0x1ff].set(table, EF::VALID);
+    }
+
     unsafe fn set_token(token: usize) {
         asm!("csrw satp, $0" :: "r"(token) :: "volatile");
     }
@@ -0,0 +1,95 @@
+use crate::consts::PAGE_SIZE;
+use crate::interrupt::{disable_and_store, restore};
+use crate::process::current_thread_mut;
+use crate::syscall::errno::SysError;
+use alloc::{string::String, vec::Vec};
+use core::mem::{size_of, MaybeUninit};
+use core::slice;
+use riscv::register::sstatus;
+
+global_asm!(include_str!("copy_user.asm"));
+
+extern "C" {
+    fn __copy_user(dst: usize, src: usize, len: usize) -> usize;
+    fn __copy_user_fault();
+    fn __copy_user_end();
+}
+
+// 检查 [addr, addr + len) 是否完全落在当前线程的用户内存区域中
+pub fn access_ok(addr: usize, len: usize, write: bool) -> Result<(), SysError> {
+    let end = addr.checked_add(len).ok_or(SysError::EFAULT)?;
+    let vm = current_thread_mut().vm.clone().ok_or(SysError::EFAULT)?;
+    if vm.lock().check_user_range(addr, end, write) {
+        Ok(())
+    } else {
+        Err(SysError::EFAULT)
+    }
+}
+
+// 只在复制期间打开 SUM，并关闭中断以免被切换到其他线程
+fn copy(dst: usize, src: usize, len: usize) -> Result<(), SysError> {
+    let flags = disable_and_store();
+    let failed = unsafe {
+        sstatus::set_sum();
+        let failed = __copy_user(dst, src, len);
+        sstatus::clear_sum();
+        failed
+    };
+    restore(flags);
+    match failed {
+        0 => Ok(()),
+        _ => Err(SysError::EFAULT),
+    }
+}
+
+pub fn copy_from_user(src: usize, dst: &mut [u8]) -> Result<(), SysError> {
+    access_ok(src, dst.len(), false)?;
+    copy(dst.as_mut_ptr() as usize, src, dst.len())
+}
+
+pub fn copy_to_user(dst: usize, src: &[u8]) -> Result<(), SysError> {
+    access_ok(dst, src.len(), true)?;
+    copy(dst, src.as_ptr() as usize, src.len())
+}
+
+pub fn read_user<T: Copy>(src: usize) -> Result<T, SysError> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let buf = unsafe { slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    copy_from_user(src, buf)?;
+    Ok(unsafe { value.assume_init() })
+}
+
+pub fn write_user<T: Copy>(dst: usize, value: &T) -> Result<(), SysError> {
+    let buf = unsafe { slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+    copy_to_user(dst, buf)
+}
+
+// 读取以 0 结尾的字符串，不含结尾的 0 最多 max 个字节，过长时返回 ENAMETOOLONG
+// 每次最多复制到页的末尾，避免越过字符串结尾访问到未映射的页
+pub fn strncpy_from_user(src: usize, max: usize) -> Result<String, SysError> {
+    let mut bytes = Vec::new();
+    let mut buf = [0u8; PAGE_SIZE];
+    loop {
+        let addr = src + bytes.len();
+        let len = (PAGE_SIZE - addr % PAGE_SIZE).min(max + 1 - bytes.len());
+        copy_from_user(addr, &mut buf[..len])?;
+        match buf[..len].iter().position(|&b| b == 0) {
+            Some(pos) => {
+                bytes.extend_from_slice(&buf[..pos]);
+                break;
+            }
+            None if bytes.len() + len > max => return Err(SysError::ENAMETOOLONG),
+            None => bytes.extend_from_slice(&buf[..len]),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| SysError::EINVAL)
+}
+
+// 复制用户内存时发生缺页，返回应当跳转到的地址
+pub fn fault_fixup(pc: usize) -> Option<usize> {
+    if pc >= __copy_user as usize && pc < __copy_user_end as usize {
+        Some(__copy_user_fault as usize)
+    } else {
+        None
+    }
+}
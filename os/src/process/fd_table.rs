@@ -0,0 +1,109 @@
+use crate::fs::file::File;
+use alloc::{sync::Arc, vec::Vec};
+use spin::Mutex;
+
+#[derive(Clone)]
+struct FdEntry {
+    file: Arc<Mutex<File>>,
+    // 带有 FD_CLOEXEC 标志的文件描述符在 exec 时不会被新线程继承
+    cloexec: bool,
+}
+
+// 文件描述符表，按需增长，文件描述符不能超过 limit
+#[derive(Clone)]
+pub struct FdTable {
+    entries: Vec<Option<FdEntry>>,
+    limit: usize,
+}
+
+impl FdTable {
+    pub fn new(limit: usize) -> Self {
+        FdTable {
+            entries: Vec::new(),
+            limit,
+        }
+    }
+
+    pub fn get(&self, fd: usize) -> Option<Arc<Mutex<File>>> {
+        self.entries
+            .get(fd)
+            .and_then(|entry| entry.as_ref())
+            .map(|entry| entry.file.clone())
+    }
+
+    // 不小于 from 的最小的空闲文件描述符，超过上限时返回 None
+    pub fn free_fd_from(&self, from: usize) -> Option<usize> {
+        let fd = (from..self.entries.len())
+            .find(|&fd| self.entries[fd].is_none())
+            .unwrap_or_else(|| from.max(self.entries.len()));
+        if fd < self.limit {
+            Some(fd)
+        } else {
+            None
+        }
+    }
+
+    // 放在最小的空闲文件描述符上，返回这个文件描述符
+    pub fn add(&mut self, file: Arc<Mutex<File>>, cloexec: bool) -> Option<usize> {
+        self.add_from(0, file, cloexec)
+    }
+
+    pub fn add_from(
+        &mut self,
+        from: usize,
+        file: Arc<Mutex<File>>,
+        cloexec: bool,
+    ) -> Option<usize> {
+        let fd = self.free_fd_from(from)?;
+        self.set(fd, file, cloexec)?;
+        Some(fd)
+    }
+
+    // 放在指定的文件描述符上，原来打开的文件被关闭
+    pub fn set(&mut self, fd: usize, file: Arc<Mutex<File>>, cloexec: bool) -> Option<()> {
+        if fd >= self.limit {
+            return None;
+        }
+        if fd >= self.entries.len() {
+            self.entries.resize(fd + 1, None);
+        }
+        self.entries[fd] = Some(FdEntry { file, cloexec });
+        Some(())
+    }
+
+    pub fn remove(&mut self, fd: usize) -> Option<Arc<Mutex<File>>> {
+        let entry = self.entries.get_mut(fd)?.take()?;
+        while let Some(None) = self.entries.last() {
+            self.entries.pop();
+        }
+        Some(entry.file)
+    }
+
+    pub fn cloexec(&self, fd: usize) -> Option<bool> {
+        Some(self.entries.get(fd)?.as_ref()?.cloexec)
+    }
+
+    pub fn set_cloexec(&mut self, fd: usize, cloexec: bool) -> Option<()> {
+        self.entries.get_mut(fd)?.as_mut()?.cloexec = cloexec;
+        Some(())
+    }
+
+    // exec 出的新线程得到的表，去掉了带有 FD_CLOEXEC 标志的文件描述符
+    pub fn inherit(&self) -> FdTable {
+        FdTable {
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| entry.clone().filter(|entry| !entry.cloexec))
+                .collect(),
+            limit: self.limit,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Arc<Mutex<File>>)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(fd, entry)| entry.as_ref().map(|entry| (fd, &entry.file)))
+    }
+}
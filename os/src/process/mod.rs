@@ -1,13 +1,22 @@
+pub mod fd_table;
 pub mod processor;
 pub mod scheduler;
+pub mod signal;
 pub mod structs;
 pub mod thread_pool;
 
-use crate::fs::{INodeExt, ROOT_INODE};
-use alloc::boxed::Box;
+use crate::cmdline::cmdline;
+use crate::context::TrapFrame;
+use crate::fs::{lookup, lookup_at, INodeExt};
+use crate::interrupt::{disable_and_store, restore};
+use crate::syscall::errno::SysError;
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use processor::Processor;
-use scheduler::RRScheduler;
-use structs::Thread;
+use rcore_fs::vfs::{FileType, INode};
+use scheduler::{FifoScheduler, RRScheduler, Scheduler};
+use signal::{bit, default_action, Action};
+use spin::Mutex;
+use structs::{load_elf, Status, Thread};
 use thread_pool::ThreadPool;
 
 pub type Tid = usize;
@@ -16,31 +25,99 @@ pub type ExitCode = usize;
 static CPU: Processor = Processor::new();
 
 pub fn init() {
-    let scheduler = RRScheduler::new(1);
-    let thread_pool = ThreadPool::new(100, Box::new(scheduler));
+    let scheduler: Box<dyn Scheduler> = match cmdline().scheduler.as_str() {
+        "fifo" => Box::new(FifoScheduler::new()),
+        "rr" => Box::new(RRScheduler::new(cmdline().time_slice)),
+        other => {
+            warn!("unknown scheduler {}, using rr", other);
+            Box::new(RRScheduler::new(cmdline().time_slice))
+        }
+    };
+    let thread_pool = ThreadPool::new(100, scheduler);
     let idle = Thread::new_kernel(Processor::idle_main as usize);
     idle.append_initial_arguments([&CPU as *const Processor as usize, 0, 0]);
     CPU.init(idle, Box::new(thread_pool));
 
-    execute("rust/user_shell", None);
+    let init = cmdline().init.clone();
+    if let Err(e) = execute(&init, &[init.clone()], &[]) {
+        panic!("failed to execute {}: {:?}", cmdline().init, e);
+    }
 
     println!("++++ setup process!   ++++");
 }
 
-pub fn execute(path: &str, host_tid: Option<Tid>) -> bool {
-    let find_result = ROOT_INODE.lookup(path);
-    match find_result {
-        Ok(inode) => {
-            let data = inode.read_as_vec().unwrap();
-            let user_thread = unsafe { Thread::new_user(data.as_slice(), host_tid) };
-            CPU.add_thread(user_thread);
-            true
-        }
-        Err(_) => {
-            println!("command not found!");
-            false
+// 创建运行 path 处程序的第一个用户线程，成功时返回它的 tid
+pub fn execute(path: &str, args: &[String], envs: &[String]) -> Result<Tid, SysError> {
+    let data = read_program(lookup(path)?)?;
+    let mut user_thread = unsafe { Thread::new_user(data.as_slice(), None, args, envs)? };
+    user_thread.trace = traced(path);
+    CPU.add_thread(user_thread).ok_or(SysError::EAGAIN)
+}
+
+// 用 path 处的程序替换当前线程的地址空间，并把 tf 改为从新程序的入口返回用户态；
+// 带有 FD_CLOEXEC 的文件描述符被关闭，被忽略的信号仍然被忽略，进程组与会话不变
+pub fn exec(
+    path: &str,
+    args: &[String],
+    envs: &[String],
+    tf: &mut TrapFrame,
+) -> Result<(), SysError> {
+    let thread = current_thread_mut();
+    let data = read_program(lookup_at(&thread.cwd(), path)?)?;
+    let (vm, entry, sp, brk) = unsafe { load_elf(data.as_slice(), args, envs)? };
+    // 先切换到新的页表，旧的地址空间才能被释放
+    unsafe {
+        vm.activate();
+    }
+    thread.vm = Some(Arc::new(Mutex::new(vm)));
+    thread.brk_start = brk;
+    thread.brk = brk;
+    let files = thread.files.lock().inherit();
+    thread.files = Arc::new(Mutex::new(files));
+    // 被跟踪的线程执行的程序也会被跟踪
+    thread.trace = thread.trace || traced(path);
+    tf.x = [0; 32];
+    tf.x[2] = sp;
+    tf.sepc = entry;
+    if let Some(parent) = thread.vfork.take() {
+        with_thread(parent, |_, parent| parent.child_changed.notify_all());
+    }
+    Ok(())
+}
+
+// fork 与 vfork，返回子线程的 tid；vfork 时子线程共享当前的地址空间，
+// 当前线程等到它执行新程序或退出后才返回
+pub fn clone(tf: &TrapFrame, vfork: bool, stack: usize) -> Result<Tid, SysError> {
+    let me = current_tid();
+    let mut child = current_thread_mut().clone_user(tf, vfork, stack)?;
+    child.wait = Some(me);
+    if vfork {
+        child.vfork = Some(me);
+    }
+    // 线程数达到上限时失败，而不是让内核崩溃
+    let tid = CPU.add_thread(child).ok_or(SysError::EAGAIN)?;
+    while vfork {
+        let flags = disable_and_store();
+        if with_thread(tid, |_, thread| thread.vfork) != Some(Some(me)) {
+            restore(flags);
+            break;
         }
+        current_thread_mut().child_changed.wait_restore(flags);
+    }
+    Ok(tid)
+}
+
+fn read_program(inode: Arc<dyn INode>) -> Result<Vec<u8>, SysError> {
+    if inode.metadata()?.type_ != FileType::File {
+        return Err(SysError::EACCES);
     }
+    Ok(inode.read_as_vec()?)
+}
+
+// 启动参数 strace 中列出的程序会被跟踪
+fn traced(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    cmdline().strace.iter().any(|s| s == path || s == name)
 }
 
 pub fn tick() {
@@ -52,16 +129,112 @@ pub fn run() {
 }
 
 pub fn exit(code: usize) {
+    if current_tid() == 1 {
+        println!("thread 1 exited, exit code = {}", code);
+        shutdown();
+    }
     CPU.exit(code);
 }
 
+// 按信号 sig 的默认动作终止当前线程，不论它是否忽略了这个信号
+pub fn kill_current(sig: usize) -> ! {
+    if current_tid() == 1 {
+        println!("thread 1 killed by signal {}", sig);
+        shutdown();
+    }
+    CPU.kill_current(sig)
+}
+
+// init 退出后不会再有用户程序运行，写回文件系统后关机
+fn shutdown() -> ! {
+    if let Err(e) = crate::fs::sync() {
+        warn!("failed to sync file systems: {:?}", e);
+    }
+    crate::sbi::shutdown()
+}
+
 pub fn yield_now() {
     CPU.yield_now();
 }
 
+pub fn sched_yield() {
+    CPU.sched_yield();
+}
+
+pub fn send_signal(tid: Tid, sig: usize) -> bool {
+    CPU.send_signal(tid, sig)
+}
+
+// 返回进程组中是否有线程
+pub fn send_signal_to_group(pgid: Tid, sig: usize) -> bool {
+    let members: Vec<Tid> = tids()
+        .into_iter()
+        .filter(|&tid| with_thread(tid, |_, thread| thread.pgid == pgid) == Some(true))
+        .collect();
+    for &tid in members.iter() {
+        send_signal(tid, sig);
+    }
+    !members.is_empty()
+}
+
+// 会话 sid 中是否有进程组 pgid
+pub fn group_in_session(pgid: Tid, sid: Tid) -> bool {
+    tids().into_iter().any(|tid| {
+        with_thread(tid, |_, thread| thread.pgid == pgid && thread.sid == sid) == Some(true)
+    })
+}
+
+pub fn signal_pending() -> bool {
+    current_thread_mut().pending != 0
+}
+
+// 返回用户态前处理当前线程待处理的信号，线程可能在这里暂停或退出
+pub fn handle_signals() {
+    loop {
+        let flags = disable_and_store();
+        let thread = current_thread_mut();
+        if thread.pending == 0 {
+            restore(flags);
+            return;
+        }
+        let sig = thread.pending.trailing_zeros() as usize + 1;
+        thread.pending &= !bit(sig);
+        match default_action(sig) {
+            Action::Terminate => {
+                restore(flags);
+                kill_current(sig);
+            }
+            Action::Stop => CPU.stop(sig),
+            Action::Continue | Action::Ignore => {}
+        }
+        restore(flags);
+    }
+}
+
 pub fn wake_up(tid: Tid) {
     CPU.wake_up(tid);
 }
+
+// 父线程取走已经结束的子线程的状态后释放它的 tid
+pub fn reap(tid: Tid) {
+    CPU.reap(tid);
+}
+pub fn scheduler_name() -> &'static str {
+    CPU.scheduler_name()
+}
+
+pub fn tids() -> Vec<Tid> {
+    CPU.tids()
+}
+
+pub fn with_thread<T>(tid: Tid, f: impl FnOnce(&Status, &Thread) -> T) -> Option<T> {
+    CPU.with_thread(tid, f)
+}
+
+pub fn with_thread_mut<T>(tid: Tid, f: impl FnOnce(&mut Thread) -> T) -> Option<T> {
+    CPU.with_thread_mut(tid, f)
+}
+
 pub fn current_tid() -> usize {
     CPU.current_tid()
 }
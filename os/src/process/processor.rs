@@ -1,9 +1,10 @@
 use crate::context::ContextContent;
 use crate::interrupt::*;
+use crate::process::signal::*;
 use crate::process::structs::*;
-use crate::process::thread_pool::ThreadPool;
+use crate::process::thread_pool::{ThreadInfo, ThreadPool};
 use crate::process::Tid;
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use core::cell::UnsafeCell;
 
 pub struct ProcessorInner {
@@ -12,6 +13,23 @@ pub struct ProcessorInner {
     current: Option<(Tid, Box<Thread>)>,
 }
 
+impl ProcessorInner {
+    // 正在运行的线程不在线程池中
+    fn thread_mut(&mut self, tid: Tid) -> Option<&mut Thread> {
+        if let Some((current, thread)) = self.current.as_mut() {
+            if *current == tid {
+                return Some(thread);
+            }
+        }
+        self.pool
+            .threads
+            .get_mut(tid)?
+            .as_mut()?
+            .thread
+            .as_deref_mut()
+    }
+}
+
 pub struct Processor {
     inner: UnsafeCell<Option<ProcessorInner>>,
 }
@@ -41,8 +59,8 @@ impl Processor {
             .expect("Processor is not initialized!")
     }
 
-    pub fn add_thread(&self, thread: Box<Thread>) {
-        self.inner().pool.add(thread);
+    pub fn add_thread(&self, thread: Box<Thread>) -> Option<Tid> {
+        self.inner().pool.add(thread)
     }
 
     pub fn idle_main(&self) -> ! {
@@ -82,14 +100,34 @@ impl Processor {
 
     pub fn exit(&self, code: usize) -> ! {
         disable_and_store();
+        println!("thread {} exited, exit code = {}", self.current_tid(), code);
+        self.terminate(exited_status(code))
+    }
+
+    pub fn kill_current(&self, sig: usize) -> ! {
+        disable_and_store();
+        println!("thread {} killed by signal {}", self.current_tid(), sig);
+        self.terminate(signaled_status(sig))
+    }
+
+    // status 留给父线程的 wait4 取走，没有父线程时 tid 立即被释放；
+    // 还没有被取走的子线程不会再被等待，它们的 tid 也被释放
+    fn terminate(&self, status: usize) -> ! {
         let inner = self.inner();
         let tid = inner.current.as_ref().unwrap().0;
 
-        inner.pool.exit(tid);
-        println!("thread {} exited, exit code = {}", tid, code);
+        inner.pool.exit(tid, status);
 
-        if let Some(wait) = inner.current.as_ref().unwrap().1.wait {
-            inner.pool.wakeup(wait);
+        let thread = &mut inner.current.as_mut().unwrap().1;
+        for (child, _) in thread.exited.drain(..) {
+            inner.pool.remove(child);
+        }
+        let wait = thread.wait;
+        if let Some(parent) = wait.and_then(|wait| inner.thread_mut(wait)) {
+            parent.exited.push((tid, status));
+            parent.child_changed.notify_all();
+        } else {
+            inner.pool.remove(tid);
         }
 
         inner.current.as_mut().unwrap().1.switch_to(&mut inner.idle);
@@ -124,11 +162,113 @@ impl Processor {
         }
     }
 
+    // 暂停当前线程并通知等待它的线程，收到 SIGCONT 后返回
+    pub fn stop(&self, sig: usize) {
+        let inner = self.inner();
+        let flags = disable_and_store();
+        let (tid, thread) = inner.current.as_mut().unwrap();
+        inner.pool.threads[*tid]
+            .as_mut()
+            .expect("thread not existed when stopping")
+            .status = Status::Stopped(sig);
+        if let Some(parent) = thread.wait.and_then(|wait| inner.thread_mut(wait)) {
+            parent.child_changed.notify_all();
+        }
+        inner.current.as_mut().unwrap().1.switch_to(&mut inner.idle);
+        restore(flags);
+    }
+
+    // 让出 CPU 但仍然参与调度
+    pub fn sched_yield(&self) {
+        let inner = self.inner();
+        if !inner.current.is_none() {
+            let flags = disable_and_store();
+            inner.current.as_mut().unwrap().1.switch_to(&mut inner.idle);
+            restore(flags);
+        }
+    }
+
+    // 信号在线程返回用户态时处理；睡眠的线程被唤醒，让阻塞的系统调用返回 EINTR
+    // 线程不存在时返回 false
+    pub fn send_signal(&self, tid: Tid, sig: usize) -> bool {
+        let inner = self.inner();
+        let flags = disable_and_store();
+        let thread = match inner.thread_mut(tid) {
+            Some(thread) => thread,
+            None => {
+                restore(flags);
+                return false;
+            }
+        };
+        if sig != 0 {
+            let action = default_action(sig);
+            match action {
+                Action::Continue => {
+                    thread.pending &= !(bit(SIGSTOP) | bit(SIGTSTP) | bit(SIGTTIN) | bit(SIGTTOU))
+                }
+                Action::Stop => thread.pending &= !bit(SIGCONT),
+                _ => {}
+            }
+            let ignored = thread.ignored & bit(sig) != 0 && can_ignore(sig);
+            if !ignored && action != Action::Ignore && action != Action::Continue {
+                thread.pending |= bit(sig);
+                inner.pool.wakeup(tid);
+            }
+            if action == Action::Continue || sig == SIGKILL {
+                inner.pool.resume(tid);
+            }
+        }
+        restore(flags);
+        true
+    }
+
     pub fn wake_up(&self, tid: Tid) {
         let inner = self.inner();
         inner.pool.wakeup(tid);
     }
 
+    pub fn reap(&self, tid: Tid) {
+        self.inner().pool.remove(tid);
+    }
+
+    pub fn scheduler_name(&self) -> &'static str {
+        self.inner().pool.scheduler_name()
+    }
+
+    // 所有存在的线程，包括正在运行的线程，不包括已经结束的线程
+    pub fn tids(&self) -> Vec<Tid> {
+        let inner = self.inner();
+        inner
+            .pool
+            .threads
+            .iter()
+            .enumerate()
+            .filter(|(_, info)| match info {
+                Some(ThreadInfo {
+                    status: Status::Exited(_),
+                    ..
+                })
+                | None => false,
+                Some(_) => true,
+            })
+            .map(|(tid, _)| tid)
+            .collect()
+    }
+
+    pub fn with_thread<T>(&self, tid: Tid, f: impl FnOnce(&Status, &Thread) -> T) -> Option<T> {
+        let inner = self.inner();
+        let info = inner.pool.threads.get(tid)?.as_ref()?;
+        match (info.thread.as_ref(), inner.current.as_ref()) {
+            (Some(thread), _) => Some(f(&info.status, thread)),
+            (None, Some((current, thread))) if *current == tid => Some(f(&info.status, thread)),
+            _ => None,
+        }
+    }
+
+    pub fn with_thread_mut<T>(&self, tid: Tid, f: impl FnOnce(&mut Thread) -> T) -> Option<T> {
+        self.inner().thread_mut(tid).map(f)
+    }
+
     pub fn current_tid(&self) -> usize {
         self.inner().current.as_mut().unwrap().0 as usize
     }
@@ -1,11 +1,12 @@
 use super::Tid;
-use alloc::vec::Vec;
+use alloc::{collections::VecDeque, vec::Vec};
 
 pub trait Scheduler {
     fn push(&mut self, tid: Tid);
     fn pop(&mut self) -> Option<Tid>;
     fn tick(&mut self) -> bool;
     fn exit(&mut self, tid: Tid);
+    fn name(&self) -> &'static str;
 }
 
 #[derive(Default)]
@@ -44,6 +45,10 @@ impl Scheduler for RRScheduler {
         if tid + 1 > self.threads.len() {
             self.threads.resize_with(tid + 1, Default::default);
         }
+        // 已经在队列中
+        if self.threads[tid].valid {
+            return;
+        }
 
         if self.threads[tid].time == 0 {
             self.threads[tid].time = self.max_time;
@@ -94,4 +99,40 @@ impl Scheduler for RRScheduler {
             self.current = 0;
         }
     }
+
+    fn name(&self) -> &'static str {
+        "rr"
+    }
+}
+
+// 先来先服务，不会因时钟中断而切换线程
+#[derive(Default)]
+pub struct FifoScheduler {
+    queue: VecDeque<Tid>,
+}
+
+impl FifoScheduler {
+    pub fn new() -> Self {
+        FifoScheduler::default()
+    }
+}
+
+impl Scheduler for FifoScheduler {
+    fn push(&mut self, tid: Tid) {
+        self.queue.push_back(tid);
+    }
+
+    fn pop(&mut self) -> Option<Tid> {
+        self.queue.pop_front()
+    }
+
+    fn tick(&mut self) -> bool {
+        false
+    }
+
+    fn exit(&mut self, _tid: Tid) {}
+
+    fn name(&self) -> &'static str {
+        "fifo"
+    }
 }
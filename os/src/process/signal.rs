@@ -0,0 +1,56 @@
+// 信号编号与 Linux 相同
+pub const SIGINT: usize = 2;
+pub const SIGQUIT: usize = 3;
+pub const SIGKILL: usize = 9;
+pub const SIGSEGV: usize = 11;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+pub const SIGTTIN: usize = 21;
+pub const SIGTTOU: usize = 22;
+pub const SIGWINCH: usize = 28;
+pub const NSIG: usize = 64;
+
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    Terminate,
+    Stop,
+    Continue,
+    Ignore,
+}
+
+// 还不能调用用户的信号处理函数，除被忽略的信号外都按默认动作处理
+pub fn default_action(sig: usize) -> Action {
+    match sig {
+        SIGCHLD | SIGWINCH => Action::Ignore,
+        SIGCONT => Action::Continue,
+        SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => Action::Stop,
+        _ => Action::Terminate,
+    }
+}
+
+// SIGKILL 与 SIGSTOP 不能被忽略
+pub fn can_ignore(sig: usize) -> bool {
+    sig != SIGKILL && sig != SIGSTOP
+}
+
+pub fn bit(sig: usize) -> u64 {
+    1 << (sig - 1)
+}
+
+// wait4 返回的状态，编码与 Linux 相同
+pub fn exited_status(code: usize) -> usize {
+    (code & 0xff) << 8
+}
+
+pub fn signaled_status(sig: usize) -> usize {
+    sig & 0x7f
+}
+
+pub fn stopped_status(sig: usize) -> usize {
+    (sig << 8) | 0x7f
+}
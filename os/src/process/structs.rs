@@ -1,9 +1,10 @@
 use super::{ExitCode, Tid};
-use crate::alloc::alloc::{alloc, dealloc, Layout};
 use crate::consts::*;
-use crate::context::Context;
+use crate::context::{Context, TrapFrame};
+use crate::memory::kernel_stack;
+use crate::timer::TICKS_PER_SECOND;
 use crate::memory::memory_set::{attr::MemoryAttr, handler::ByFrame, MemorySet};
-use alloc::boxed::Box;
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
 use core::str;
 use riscv::register::satp;
 use xmas_elf::{
@@ -11,15 +12,22 @@ use xmas_elf::{
     program::{Flags, SegmentData, Type},
     ElfFile,
 };
-use crate::fs::file::File;
+use super::fd_table::FdTable;
+use crate::fs::file::{File, FileDescriptorType};
+use crate::fs::{devfs::fill_random, ROOT_INODE};
+use crate::sync::condvar::Condvar;
+use rcore_fs::vfs::INode;
 use spin::Mutex;
 use alloc::sync::Arc;
+use crate::syscall::errno::SysError;
 
 #[derive(Clone)]
 pub enum Status {
     Ready,
     Running(Tid),
     Sleeping,
+    // 被信号暂停，收到 SIGCONT 后继续
+    Stopped(usize),
     Exited(ExitCode),
 }
 
@@ -27,7 +35,28 @@ pub struct Thread {
     pub context: Context,
     pub kstack: KernelStack,
     pub wait: Option<Tid>,
-    pub ofile: [Option<Arc<Mutex<File>>>; NOFILE],
+    // 同一进程的线程可以共享文件描述符表
+    pub files: Arc<Mutex<FdTable>>,
+    pub vm: Option<Arc<Mutex<MemorySet>>>,
+    // 当前工作目录，None 表示根目录
+    pub cwd: Option<Arc<dyn INode>>,
+    // 是否输出每次系统调用的参数与结果
+    pub trace: bool,
+    // 堆的起点与当前的 program break
+    pub brk_start: usize,
+    pub brk: usize,
+    // 所属的进程组与会话，0 表示在加入线程池时自成一组
+    pub pgid: Tid,
+    pub sid: Tid,
+    // 待处理的信号与被忽略的信号，第 n 位对应信号 n + 1
+    pub pending: u64,
+    pub ignored: u64,
+    // 已经结束、还没有被 wait4 取走的子线程与它们的状态
+    pub exited: Vec<(Tid, usize)>,
+    // 由 vfork 创建、还与父线程共享地址空间时为父线程，执行新程序或退出时唤醒它
+    pub vfork: Option<Tid>,
+    // 子线程结束、暂停或 vfork 出的子线程执行新程序时被通知
+    pub child_changed: Condvar,
 }
 
 impl Thread {
@@ -44,7 +73,19 @@ impl Thread {
                 context: Context::new_kernel_thread(entry, kstack_.top(), satp::read().bits()),
                 kstack: kstack_,
                 wait: None,
-                ofile: [None; NOFILE],
+                files: Arc::new(Mutex::new(FdTable::new(NOFILE))),
+                vm: None,
+                cwd: None,
+                trace: false,
+                brk_start: 0,
+                brk: 0,
+                pgid: 0,
+                sid: 0,
+                pending: 0,
+                ignored: 0,
+                exited: Vec::new(),
+                vfork: None,
+                child_changed: Condvar::new(),
             })
         }
     }
@@ -54,7 +95,19 @@ impl Thread {
             context: Context::null(),
             kstack: KernelStack::new_empty(),
             wait: None,
-            ofile: [None; NOFILE],
+            files: Arc::new(Mutex::new(FdTable::new(NOFILE))),
+            vm: None,
+            cwd: None,
+            trace: false,
+            brk_start: 0,
+            brk: 0,
+            pgid: 0,
+            sid: 0,
+            pending: 0,
+            ignored: 0,
+            exited: Vec::new(),
+            vfork: None,
+            child_changed: Condvar::new(),
         })
     }
 
@@ -64,77 +117,99 @@ impl Thread {
         }
     }
 
-    pub unsafe fn new_user(data: &[u8], wait_thread: Option<Tid>) -> Box<Thread> {
-        let elf = ElfFile::new(data).expect("failed to analyse elf!");
-
-        match elf.header.pt2.type_().as_type() {
-            header::Type::Executable => {
-                // println!("it really a executable!");
-            }
-            header::Type::SharedObject => {
-                panic!("shared object is not supported!");
-            }
-            _ => {
-                panic!("unsupported elf type!");
-            }
-        }
-        let entry_addr = elf.header.pt2.entry_point() as usize;
-        let mut vm = elf.make_memory_set();
-
-        let mut ustack_top = {
-            let (ustack_bottom, ustack_top) =
-                (USER_STACK_OFFSET, USER_STACK_OFFSET + USER_STACK_SIZE);
-            vm.push(
-                ustack_bottom,
-                ustack_top,
-                MemoryAttr::new().set_user(),
-                ByFrame::new(),
-                None,
-            );
-            ustack_top
-        };
+    // data 不是可执行的 ELF 文件时返回 ENOEXEC
+    pub unsafe fn new_user(
+        data: &[u8],
+        wait_thread: Option<Tid>,
+        args: &[String],
+        envs: &[String],
+    ) -> Result<Box<Thread>, SysError> {
+        let (vm, entry_addr, sp, brk) = load_elf(data, args, envs)?;
 
         let kstack = KernelStack::new();
 
-        let mut thread = Thread {
-            context: Context::new_user_thread(entry_addr, ustack_top, kstack.top(), vm.token()),
+        let thread = Thread {
+            context: Context::new_user_thread(entry_addr, sp, kstack.top(), vm.token()),
             kstack: kstack,
             wait: wait_thread,
-            ofile: [None; NOFILE],
+            files: Arc::new(Mutex::new(FdTable::new(NOFILE))),
+            vm: Some(Arc::new(Mutex::new(vm))),
+            cwd: None,
+            trace: false,
+            brk_start: brk,
+            brk,
+            pgid: 0,
+            sid: 0,
+            pending: 0,
+            ignored: 0,
+            exited: Vec::new(),
+            vfork: None,
+            child_changed: Condvar::new(),
         };
-        for i in 0..3 {
-            thread.ofile[i] = Some(Arc::new(Mutex::new(File::default())));
+        // 标准输入、输出与错误都指向 /dev/console
+        let console = crate::fs::lookup("/dev/console").expect("/dev/console not found!");
+        for _ in 0..3 {
+            let mut file = File::default();
+            file.set_fdtype(FileDescriptorType::FD_DEVICE);
+            file.set_readable(true);
+            file.set_writable(true);
+            file.inode = Some(console.clone());
+            thread.files.lock().add(Arc::new(Mutex::new(file)), false);
         }
-        Box::new(thread)
-        
+        Ok(Box::new(thread))
     }
 
-    // 分配文件描述符
-    pub fn alloc_fd(&mut self) -> i32 {
-        let mut fd = 0;
-        for i in 0usize..NOFILE {
-            if self.ofile[i].is_none() {
-                fd = i;
-                break;
-            }
+    // fork 与 vfork 出的子线程，从同一个系统调用返回 0；share_vm 时与父线程共享地址空间，
+    // 否则复制它；文件描述符表被复制，其中打开的文件仍与父线程共享
+    // stack 不为 0 时作为子线程的用户栈指针
+    pub fn clone_user(
+        &self,
+        tf: &TrapFrame,
+        share_vm: bool,
+        stack: usize,
+    ) -> Result<Box<Thread>, SysError> {
+        let vm = self.vm.as_ref().ok_or(SysError::EINVAL)?;
+        let vm = if share_vm {
+            vm.clone()
+        } else {
+            Arc::new(Mutex::new(vm.lock().try_clone()?))
+        };
+        let mut tf = tf.clone();
+        tf.x[10] = 0;
+        if stack != 0 {
+            tf.x[2] = stack;
         }
-        self.ofile[fd] = Some(Arc::new(Mutex::new(File::default())));
-        fd as i32
+        let kstack = KernelStack::new();
+        let token = vm.lock().token();
+        Ok(Box::new(Thread {
+            context: unsafe { Context::new_fork(tf, kstack.top(), token) },
+            kstack,
+            wait: None,
+            files: Arc::new(Mutex::new(self.files.lock().clone())),
+            vm: Some(vm),
+            cwd: self.cwd.clone(),
+            trace: self.trace,
+            brk_start: self.brk_start,
+            brk: self.brk,
+            pgid: self.pgid,
+            sid: self.sid,
+            pending: 0,
+            ignored: self.ignored,
+            exited: Vec::new(),
+            vfork: None,
+            child_changed: Condvar::new(),
+        }))
     }
-    // 回收文件描述符
-    pub fn dealloc_fd(&mut self, fd: i32) {
-        assert!(self.ofile[fd as usize].is_some());
-        self.ofile[fd as usize] = None;
+
+    pub fn cwd(&self) -> Arc<dyn INode> {
+        self.cwd.clone().unwrap_or_else(|| ROOT_INODE.clone())
     }
 }
 
 pub struct KernelStack(usize);
 impl KernelStack {
     pub fn new() -> Self {
-        let bottom = unsafe {
-            alloc(Layout::from_size_align(KERNEL_STACK_SIZE, KERNEL_STACK_SIZE).unwrap()) as usize
-        };
-        KernelStack(bottom)
+        KernelStack(kernel_stack::alloc())
     }
     pub fn new_empty() -> Self {
         KernelStack(0)
@@ -147,43 +222,211 @@ impl KernelStack {
 impl Drop for KernelStack {
     fn drop(&mut self) {
         if self.0 != 0 {
-            unsafe {
-                dealloc(
-                    self.0 as _,
-                    Layout::from_size_align(KERNEL_STACK_SIZE, KERNEL_STACK_SIZE).unwrap(),
-                );
-            }
+            kernel_stack::dealloc(self.0);
+        }
+    }
+}
+
+// 返回地址空间、入口地址、栈指针与堆的起点
+// 参数、环境变量与辅助向量按 Linux 的约定放在用户栈上
+pub unsafe fn load_elf(
+    data: &[u8],
+    args: &[String],
+    envs: &[String],
+) -> Result<(MemorySet, usize, usize, usize), SysError> {
+    // 只支持 64 位的 ELF 文件
+    if data.get(4) != Some(&ELFCLASS64) {
+        return Err(SysError::ENOEXEC);
+    }
+    let elf = ElfFile::new(data).map_err(|_| SysError::ENOEXEC)?;
+    elf.check()?;
+
+    match elf.header.pt2.type_().as_type() {
+        header::Type::Executable => {
+            // println!("it really a executable!");
+        }
+        header::Type::SharedObject => {
+            warn!("shared object is not supported!");
+            return Err(SysError::ENOEXEC);
+        }
+        _ => {
+            warn!("unsupported elf type!");
+            return Err(SysError::ENOEXEC);
         }
     }
+    let entry_addr = elf.header.pt2.entry_point() as usize;
+    let (mut vm, brk) = elf.make_memory_set()?;
+
+    let ustack_top = {
+        let (ustack_bottom, ustack_top) = (USER_STACK_OFFSET, USER_STACK_OFFSET + USER_STACK_SIZE);
+        vm.try_push(
+            ustack_bottom,
+            ustack_top,
+            MemoryAttr::new().set_user(),
+            ByFrame::new(),
+            None,
+        )?;
+        ustack_top
+    };
+
+    let (sp, stack) = init_stack(ustack_top, args, envs, &elf.auxv(entry_addr));
+    vm.write(sp, &stack);
+    Ok((vm, entry_addr, sp, brk))
 }
 
+// 辅助向量的类型
+const AT_NULL: usize = 0;
+const AT_PHDR: usize = 3;
+const AT_PHENT: usize = 4;
+const AT_PHNUM: usize = 5;
+const AT_PAGESZ: usize = 6;
+const AT_ENTRY: usize = 9;
+const AT_UID: usize = 11;
+const AT_EUID: usize = 12;
+const AT_GID: usize = 13;
+const AT_EGID: usize = 14;
+const AT_CLKTCK: usize = 17;
+const AT_RANDOM: usize = 25;
+
+// 从 top 向下依次放置字符串与 16 字节随机数，再放置 argc、argv、envp 与 auxv
+// 返回栈指针与 [sp, top) 的内容，AT_RANDOM 的值由这里填写
+fn init_stack(
+    top: usize,
+    args: &[String],
+    envs: &[String],
+    auxv: &[(usize, usize)],
+) -> (usize, Vec<u8>) {
+    let mut strings = vec![0u8; 16];
+    fill_random(&mut strings);
+    let mut offsets = Vec::new();
+    for s in args.iter().chain(envs.iter()) {
+        offsets.push(strings.len());
+        strings.extend_from_slice(s.as_bytes());
+        strings.push(0);
+    }
+    let base = (top - strings.len()) & !0xf;
+
+    let mut words = vec![args.len()];
+    words.extend(offsets[..args.len()].iter().map(|offset| base + offset));
+    words.push(0);
+    words.extend(offsets[args.len()..].iter().map(|offset| base + offset));
+    words.push(0);
+    for &(type_, value) in auxv {
+        words.push(type_);
+        words.push(if type_ == AT_RANDOM { base } else { value });
+    }
+    words.push(AT_NULL);
+    words.push(0);
+
+    let sp = (base - words.len() * 8) & !0xf;
+    let mut stack = vec![0u8; top - sp];
+    for (i, word) in words.iter().enumerate() {
+        stack[i * 8..i * 8 + 8].copy_from_slice(&word.to_le_bytes());
+    }
+    stack[base - sp..base - sp + strings.len()].copy_from_slice(&strings);
+    (sp, stack)
+}
+
+// e_ident[EI_CLASS] 与 ELF64 程序头的大小
+const ELFCLASS64: u8 = 2;
+const PH_ENTRY_SIZE: usize = 56;
+
 trait ElfExt {
-    fn make_memory_set(&self) -> MemorySet;
+    fn check(&self) -> Result<(), SysError>;
+    fn make_memory_set(&self) -> Result<(MemorySet, usize), SysError>;
+    fn auxv(&self, entry: usize) -> Vec<(usize, usize)>;
 }
 
 impl ElfExt for ElfFile<'_> {
-    fn make_memory_set(&self) -> MemorySet {
-        let mut memory_set = MemorySet::new();
+    // xmas-elf 按文件中的偏移取出程序头与段的内容时不检查越界，先检查它们都在文件中，
+    // 且段在文件中的部分不比在内存中的长
+    fn check(&self) -> Result<(), SysError> {
+        let pt2 = &self.header.pt2;
+        let offset = pt2.ph_offset() as usize;
+        let table_end = (pt2.ph_count() as usize)
+            .checked_mul(PH_ENTRY_SIZE)
+            .and_then(|size| offset.checked_add(size));
+        if pt2.ph_entry_size() as usize != PH_ENTRY_SIZE
+            || offset % 8 != 0
+            || table_end.map_or(true, |end| end > self.input.len())
+        {
+            return Err(SysError::ENOEXEC);
+        }
         for ph in self.program_iter() {
             if ph.get_type() != Ok(Type::Load) {
                 continue;
             }
+            let end = (ph.offset() as usize).checked_add(ph.file_size() as usize);
+            if end.map_or(true, |end| end > self.input.len()) || ph.file_size() > ph.mem_size() {
+                return Err(SysError::ENOEXEC);
+            }
+        }
+        Ok(())
+    }
+
+    // 同时返回各段结束处向上对齐到页的地址，作为堆的起点
+    // 段不在用户地址空间中或相互重叠时返回 ENOEXEC
+    fn make_memory_set(&self) -> Result<(MemorySet, usize), SysError> {
+        let mut memory_set = MemorySet::new();
+        let mut end = 0;
+        for ph in self.program_iter() {
+            if ph.get_type() != Ok(Type::Load) || ph.mem_size() == 0 {
+                continue;
+            }
             let vaddr = ph.virtual_addr() as usize;
             let mem_size = ph.mem_size() as usize;
-            let data = match ph.get_data(self).unwrap() {
-                SegmentData::Undefined(data) => data,
-                _ => unreachable!(),
+            let data = match ph.get_data(self) {
+                Ok(SegmentData::Undefined(data)) => data,
+                _ => return Err(SysError::ENOEXEC),
             };
 
-            memory_set.push(
+            let in_user = vaddr
+                .checked_add(mem_size)
+                .map_or(false, |end| end <= USER_STACK_OFFSET);
+            if !in_user || !memory_set.test_free_area(vaddr, vaddr + mem_size) {
+                return Err(SysError::ENOEXEC);
+            }
+            // 段的起始地址不一定按页对齐，数据要写到 vaddr 处，其余部分为 0
+            memory_set.try_push(
                 vaddr,
                 vaddr + mem_size,
                 ph.flags().to_attr(),
                 ByFrame::new(),
-                Some((data.as_ptr() as usize, data.len())),
-            );
+                None,
+            )?;
+            memory_set.write(vaddr, data);
+            end = end.max((vaddr + mem_size - 1) / PAGE_SIZE * PAGE_SIZE + PAGE_SIZE);
         }
-        memory_set
+        Ok((memory_set, end))
+    }
+
+    fn auxv(&self, entry: usize) -> Vec<(usize, usize)> {
+        let pt2 = &self.header.pt2;
+        let ph_offset = pt2.ph_offset() as usize;
+        // 程序头表所在的 LOAD 段决定了它被加载到的地址
+        let phdr = self
+            .program_iter()
+            .filter(|ph| ph.get_type() == Ok(Type::Load))
+            .find(|ph| {
+                let offset = ph.offset() as usize;
+                offset <= ph_offset && ph_offset < offset + ph.file_size() as usize
+            })
+            .map_or(0, |ph| {
+                ph.virtual_addr() as usize + ph_offset - ph.offset() as usize
+            });
+        vec![
+            (AT_PHDR, phdr),
+            (AT_PHENT, pt2.ph_entry_size() as usize),
+            (AT_PHNUM, pt2.ph_count() as usize),
+            (AT_PAGESZ, PAGE_SIZE),
+            (AT_ENTRY, entry),
+            (AT_UID, 0),
+            (AT_EUID, 0),
+            (AT_GID, 0),
+            (AT_EGID, 0),
+            (AT_CLKTCK, TICKS_PER_SECOND),
+            (AT_RANDOM, 0),
+        ]
     }
 }
 
@@ -24,39 +24,52 @@ impl ThreadPool {
             scheduler,
         }
     }
-    fn alloc_tid(&self) -> Tid {
-        for (i, info) in self.threads.iter().enumerate() {
-            if info.is_none() {
-                return i;
-            }
-        }
-        panic!("alloc tid failed!");
+    // 与 Linux 一样从 1 开始分配，0 在 kill、setpgid 等调用中另有含义
+    fn alloc_tid(&self) -> Option<Tid> {
+        (1..self.threads.len()).find(|&i| self.threads[i].is_none())
     }
 
-    pub fn add(&mut self, _thread: Box<Thread>) {
-        let tid = self.alloc_tid();
+    // 线程池已满时返回 None
+    pub fn add(&mut self, mut _thread: Box<Thread>) -> Option<Tid> {
+        let tid = self.alloc_tid()?;
+        if _thread.pgid == 0 {
+            _thread.pgid = tid;
+            _thread.sid = tid;
+        }
         self.threads[tid] = Some(ThreadInfo {
             status: Status::Ready,
             thread: Some(_thread),
         });
         self.scheduler.push(tid);
+        Some(tid)
     }
 
+    // 运行时被唤醒的线程会留在调度队列中，之后可能已经退出、被暂停或正在运行，跳过它们
     pub fn acquire(&mut self) -> Option<(Tid, Box<Thread>)> {
-        if let Some(tid) = self.scheduler.pop() {
-            let mut thread_info = self.threads[tid].as_mut().expect("thread not exist!");
+        while let Some(tid) = self.scheduler.pop() {
+            let thread_info = match self.threads[tid].as_mut() {
+                Some(info) if info.thread.is_some() => info,
+                _ => continue,
+            };
+            match thread_info.status {
+                Status::Ready | Status::Sleeping => {}
+                _ => continue,
+            }
             thread_info.status = Status::Running(tid);
             return Some((tid, thread_info.thread.take().expect("thread not exist!")));
-        } else {
-            return None;
         }
+        None
     }
 
+    // 已经结束的线程在这里被释放
     pub fn retrieve(&mut self, tid: Tid, thread: Box<Thread>) {
-        if self.threads[tid].is_none() {
+        let thread_info = match self.threads[tid].as_mut() {
+            Some(info) => info,
+            None => return,
+        };
+        if let Status::Exited(_) = thread_info.status {
             return;
         }
-        let mut thread_info = self.threads[tid].as_mut().expect("thread not exist!");
         thread_info.thread = Some(thread);
         if let Status::Running(_) = thread_info.status {
             thread_info.status = Status::Ready;
@@ -69,16 +82,60 @@ impl ThreadPool {
         ret
     }
 
-    pub fn exit(&mut self, tid: Tid) {
-        self.threads[tid] = None;
+    // 结束的线程保留 tid，直到父线程用 wait4 取走它的状态；子线程不再有父线程等待
+    pub fn exit(&mut self, tid: Tid, status: usize) {
+        self.threads[tid] = Some(ThreadInfo {
+            status: Status::Exited(status),
+            thread: None,
+        });
         self.scheduler.exit(tid);
+        for thread in self
+            .threads
+            .iter_mut()
+            .filter_map(|info| info.as_mut()?.thread.as_mut())
+        {
+            if thread.wait == Some(tid) {
+                thread.wait = None;
+            }
+        }
+    }
+
+    // 释放已经结束的线程的 tid
+    pub fn remove(&mut self, tid: Tid) {
+        if let Some(ThreadInfo {
+            status: Status::Exited(_),
+            ..
+        }) = self.threads[tid]
+        {
+            self.threads[tid] = None;
+        }
     }
 
+    pub fn scheduler_name(&self) -> &'static str {
+        self.scheduler.name()
+    }
+
+    // 等待队列中可能留有已经退出的线程；已在调度队列中与被暂停的线程不必唤醒
     pub fn wakeup(&mut self, tid: Tid) {
-        let proc = self.threads[tid]
-            .as_mut()
-            .expect("thread not exist when waking up");
+        let proc = match self.threads[tid].as_mut() {
+            Some(proc) => proc,
+            None => return,
+        };
+        match proc.status {
+            Status::Ready | Status::Stopped(_) | Status::Exited(_) => return,
+            _ => {}
+        }
         proc.status = Status::Ready;
         self.scheduler.push(tid);
     }
+
+    // 收到 SIGCONT 或 SIGKILL 的暂停线程重新参与调度
+    pub fn resume(&mut self, tid: Tid) {
+        if let Some(proc) = self.threads[tid].as_mut() {
+            if let Status::Stopped(_) = proc.status {
+                proc.status = Status::Ready;
+                self.scheduler.push(tid);
+            }
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::interrupt::{disable_and_store, restore};
 use crate::process::{current_tid, wake_up, yield_now, Tid};
 use alloc::collections::VecDeque;
 use spin::Mutex;
@@ -12,9 +13,17 @@ impl Condvar {
         Condvar::default()
     }
 
+    // 加入等待队列与睡眠之间不能被中断，否则可能错过唤醒
     pub fn wait(&self) {
+        self.wait_restore(disable_and_store());
+    }
+
+    // 调用者关闭中断后检查等待条件，条件不满足时调用，醒来后以 flags 恢复中断
+    // 从检查到加入等待队列之间不会被唤醒者抢先
+    pub fn wait_restore(&self, flags: usize) {
         self.wait_queue.lock().push_back(current_tid());
         yield_now();
+        restore(flags);
     }
 
     pub fn notify(&self) {
@@ -24,4 +33,12 @@ impl Condvar {
         }
         /* yield_now(); */
     }
+
+    pub fn notify_all(&self) {
+        let queue: VecDeque<Tid> =
+            core::mem::replace(&mut *self.wait_queue.lock(), VecDeque::new());
+        for tid in queue {
+            wake_up(tid);
+        }
+    }
 }
@@ -0,0 +1,75 @@
+use crate::context::TrapFrame;
+use crate::memory::uaccess::strncpy_from_user;
+use alloc::string::String;
+use core::fmt::Write;
+
+// 跟踪系统调用时参数的显示方式
+#[derive(Copy, Clone)]
+pub enum Arg {
+    // 有符号十进制，如文件描述符与长度
+    Int,
+    // 十六进制，如用户地址
+    Hex,
+    // 八进制，如 flags 与 mode
+    Oct,
+    // 用户内存中以 0 结尾的字符串
+    Str,
+}
+
+// 系统调用的参数 a0 ~ a5，由各个系统调用按需要的类型取出
+// execve 与 clone 还需要修改或复制陷入时保存的 TrapFrame
+pub struct SyscallArgs<'a>([usize; 6], &'a mut TrapFrame);
+
+impl<'a> SyscallArgs<'a> {
+    pub fn new(args: [usize; 6], tf: &'a mut TrapFrame) -> Self {
+        SyscallArgs(args, tf)
+    }
+
+    pub fn trap_frame(&mut self) -> &mut TrapFrame {
+        self.1
+    }
+
+    pub fn usize(&self, i: usize) -> usize {
+        self.0[i]
+    }
+
+    pub fn isize(&self, i: usize) -> isize {
+        self.0[i] as isize
+    }
+
+    pub fn i32(&self, i: usize) -> i32 {
+        self.0[i] as i32
+    }
+
+    pub fn u32(&self, i: usize) -> u32 {
+        self.0[i] as u32
+    }
+
+    pub fn ptr<T>(&self, i: usize) -> *const T {
+        self.0[i] as *const T
+    }
+
+    pub fn mut_ptr<T>(&self, i: usize) -> *mut T {
+        self.0[i] as *mut T
+    }
+
+    // 形如 3, "/bin", 0o100 的参数列表
+    pub fn format(&self, kinds: &[Arg]) -> String {
+        let mut s = String::new();
+        for (i, (kind, &arg)) in kinds.iter().zip(self.0.iter()).enumerate() {
+            if i > 0 {
+                s.push_str(", ");
+            }
+            let _ = match kind {
+                Arg::Int => write!(s, "{}", arg as isize),
+                Arg::Hex => write!(s, "{:#x}", arg),
+                Arg::Oct => write!(s, "{:#o}", arg),
+                Arg::Str => match strncpy_from_user(arg, 64) {
+                    Ok(string) => write!(s, "{:?}", string),
+                    Err(_) => write!(s, "{:#x}", arg),
+                },
+            };
+        }
+        s
+    }
+}
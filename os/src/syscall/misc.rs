@@ -0,0 +1,108 @@
+use super::errno::{SysError, SysResult};
+use crate::interrupt::{disable_and_store, restore};
+use crate::memory::uaccess::{read_user, write_user};
+use crate::process;
+use crate::timer::{get_time_ns, sleep_restore};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeSpec {
+    sec: i64,
+    nsec: i64,
+}
+
+impl TimeSpec {
+    fn now() -> Self {
+        let ns = get_time_ns() as i64;
+        TimeSpec {
+            sec: ns / 1_000_000_000,
+            nsec: ns % 1_000_000_000,
+        }
+    }
+
+    fn to_ns(&self) -> u64 {
+        self.sec as u64 * 1_000_000_000 + self.nsec as u64
+    }
+}
+
+// struct utsname 的每一项都是 65 字节的字符串
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct UtsName {
+    fields: [[u8; 65]; 6],
+}
+
+// 没有实时时钟，各种时钟都从启动时开始计时
+pub fn sys_clock_gettime(_clock: usize, tp: *mut TimeSpec) -> SysResult {
+    write_user(tp as usize, &TimeSpec::now())?;
+    Ok(0)
+}
+
+// 被信号打断后重新开始计时，rem 总是不被写入
+pub fn sys_nanosleep(req: *const TimeSpec, _rem: *mut TimeSpec) -> SysResult {
+    let req: TimeSpec = read_user(req as usize)?;
+    if req.sec < 0 || req.nsec < 0 || req.nsec >= 1_000_000_000 {
+        return Err(SysError::EINVAL);
+    }
+    let deadline = get_time_ns() + req.to_ns();
+    loop {
+        // 检查与睡眠之间不能被中断，否则可能错过信号的唤醒
+        let flags = disable_and_store();
+        if get_time_ns() >= deadline {
+            restore(flags);
+            return Ok(0);
+        }
+        if process::signal_pending() {
+            restore(flags);
+            return Err(SysError::EINTR);
+        }
+        sleep_restore(deadline, flags);
+    }
+}
+
+pub fn sys_sched_yield() -> SysResult {
+    process::sched_yield();
+    Ok(0)
+}
+
+pub fn sys_uname(buf: *mut UtsName) -> SysResult {
+    let mut uts = UtsName {
+        fields: [[0; 65]; 6],
+    };
+    let values = ["rCore", "rcore", "0.1.0", "#1", "riscv64", "(none)"];
+    for (field, value) in uts.fields.iter_mut().zip(values.iter()) {
+        field[..value.len()].copy_from_slice(value.as_bytes());
+    }
+    write_user(buf as usize, &uts)?;
+    Ok(0)
+}
+
+// 目前每个进程只有一个线程，进程号就是线程号
+pub fn sys_getpid() -> SysResult {
+    Ok(process::current_tid())
+}
+
+// 调用者线程自己的 tid，即使以后同一进程中有多个线程也不共享
+pub fn sys_gettid() -> SysResult {
+    Ok(process::current_tid())
+}
+
+// 通过 exec 启动的程序，父进程是调用 exec 的线程，它退出后变为 0
+pub fn sys_getppid() -> SysResult {
+    Ok(process::current_thread_mut().wait.unwrap_or(0))
+}
+
+// 只有 root 用户
+pub fn sys_getuid() -> SysResult {
+    Ok(0)
+}
+
+// 线程退出时不会清零 tidptr，也不会唤醒等待它的 futex
+pub fn sys_set_tid_address(_tidptr: usize) -> SysResult {
+    Ok(process::current_tid())
+}
+
+// 信号屏蔽字与 robust list 还没有实现，设置被忽略
+pub fn sys_ignored() -> SysResult {
+    Ok(0)
+}
@@ -0,0 +1,159 @@
+use super::errno::{SysError, SysResult};
+use super::{seekable_inode, CHUNK_SIZE};
+use crate::consts::{PAGE_SIZE, USER_END, USER_MMAP_OFFSET};
+use crate::memory::memory_set::{attr::MemoryAttr, handler::ByFrame, MemorySet};
+use crate::process;
+use alloc::{sync::Arc, vec};
+use spin::Mutex;
+
+pub const PROT_WRITE: usize = 0x2;
+pub const PROT_EXEC: usize = 0x4;
+
+pub const MAP_SHARED: usize = 0x01;
+pub const MAP_FIXED: usize = 0x10;
+pub const MAP_ANONYMOUS: usize = 0x20;
+
+fn page_up(addr: usize) -> usize {
+    (addr + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE
+}
+
+// [addr, addr + len) 是否完全位于用户地址空间中
+fn is_user_range(addr: usize, len: usize) -> bool {
+    addr.checked_add(len).map_or(false, |end| end <= USER_END)
+}
+
+fn current_vm() -> Result<Arc<Mutex<MemorySet>>, SysError> {
+    process::current_thread_mut()
+        .vm
+        .clone()
+        .ok_or(SysError::ENOMEM)
+}
+
+// 失败或只是查询时返回当前的 program break
+pub fn sys_brk(addr: usize) -> SysResult {
+    let vm = current_vm()?;
+    let mut vm = vm.lock();
+    let thread = process::current_thread_mut();
+    if addr < thread.brk_start || addr > USER_END {
+        return Ok(thread.brk);
+    }
+    let (old_end, new_end) = (page_up(thread.brk), page_up(addr));
+    if new_end > old_end {
+        if !vm.test_free_area(old_end, new_end) {
+            return Ok(thread.brk);
+        }
+        let attr = MemoryAttr::new().set_user();
+        if vm
+            .try_push(old_end, new_end, attr, ByFrame::new(), None)
+            .is_err()
+        {
+            return Ok(thread.brk);
+        }
+    } else if new_end < old_end {
+        vm.remove(new_end, old_end);
+    }
+    thread.brk = addr;
+    Ok(addr)
+}
+
+// 文件映射在建立时读入内容，之后的修改不会写回，因此不支持共享的文件映射
+pub fn sys_mmap(
+    addr: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: usize,
+    offset: usize,
+) -> SysResult {
+    if len == 0 || len > USER_END || addr % PAGE_SIZE != 0 || offset % PAGE_SIZE != 0 {
+        return Err(SysError::EINVAL);
+    }
+    let len = page_up(len);
+    let inode = if flags & MAP_ANONYMOUS != 0 {
+        None
+    } else if flags & MAP_SHARED != 0 {
+        return Err(SysError::ENODEV);
+    } else {
+        Some(seekable_inode(fd)?)
+    };
+    let vm = current_vm()?;
+    let mut vm = vm.lock();
+    let start = if flags & MAP_FIXED != 0 {
+        if addr == 0 || !is_user_range(addr, len) {
+            return Err(SysError::EINVAL);
+        }
+        vm.remove(addr, addr + len);
+        addr
+    } else {
+        // 不在用户地址空间中的提示地址被忽略
+        let hint = if addr != 0 && is_user_range(addr, len) {
+            addr
+        } else {
+            USER_MMAP_OFFSET
+        };
+        let start = vm.find_free_area(hint, len);
+        if !is_user_range(start, len) {
+            return Err(SysError::ENOMEM);
+        }
+        start
+    };
+    vm.try_push(start, start + len, prot_attr(prot), ByFrame::new(), None)?;
+    // 文件的内容分块读入，读到文件末尾为止，其余部分为 0
+    if let Some(inode) = inode {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut pos = 0;
+        while pos < len {
+            let size = match inode.read_at(offset + pos, &mut buf) {
+                Ok(size) => size,
+                Err(e) => {
+                    vm.remove(start, start + len);
+                    return Err(e.into());
+                }
+            };
+            vm.write(start + pos, &buf[..size.min(len - pos)]);
+            pos += size;
+            if size < CHUNK_SIZE {
+                break;
+            }
+        }
+    }
+    Ok(start)
+}
+
+pub fn sys_munmap(addr: usize, len: usize) -> SysResult {
+    if addr % PAGE_SIZE != 0 || len == 0 || len > USER_END || !is_user_range(addr, page_up(len)) {
+        return Err(SysError::EINVAL);
+    }
+    current_vm()?.lock().remove(addr, addr + page_up(len));
+    Ok(0)
+}
+
+// 范围内有未映射的页时返回 ENOMEM
+pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> SysResult {
+    if addr % PAGE_SIZE != 0 || len > USER_END || !is_user_range(addr, page_up(len)) {
+        return Err(SysError::EINVAL);
+    }
+    if len == 0 {
+        return Ok(0);
+    }
+    let end = addr + page_up(len);
+    let vm = current_vm()?;
+    let mut vm = vm.lock();
+    if !vm.check_user_range(addr, end, false) {
+        return Err(SysError::ENOMEM);
+    }
+    vm.protect(addr, end, prot_attr(prot));
+    Ok(0)
+}
+
+// 页表项无法表示不可读的页，PROT_NONE 与只有 PROT_WRITE 时分别按只读与可读写处理
+fn prot_attr(prot: usize) -> MemoryAttr {
+    let mut attr = MemoryAttr::new().set_user();
+    if prot & PROT_WRITE == 0 {
+        attr = attr.set_readonly();
+    }
+    if prot & PROT_EXEC != 0 {
+        attr = attr.set_execute();
+    }
+    attr
+}
@@ -0,0 +1,821 @@
+mod args;
+pub mod errno;
+mod misc;
+mod mm;
+mod proc;
+
+use crate::consts::PAGE_SIZE;
+use crate::context::TrapFrame;
+use crate::fs::file::{File, FileDescriptorType, O_APPEND, O_CLOEXEC};
+use crate::fs::pipe::{Pipe, PipeEnd};
+use crate::fs::stat::{dirent_type, Stat};
+use crate::fs::tty;
+use crate::fs::{lookup_at, lookup_parent, mount, path_of, ramfs::RamFs};
+use crate::memory::uaccess::{
+    access_ok, copy_from_user, copy_to_user, read_user, strncpy_from_user, write_user,
+};
+use crate::process;
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
+use args::{Arg, SyscallArgs};
+use errno::{SysError, SysResult};
+use misc::*;
+use mm::*;
+use proc::*;
+use rcore_fs::vfs::{FileSystem, FileType, INode};
+use spin::Mutex;
+
+pub const PATH_MAX: usize = 4096;
+// execve 的参数与环境变量各自最多的个数
+pub const ARG_MAX: usize = 256;
+pub const IOV_MAX: usize = 1024;
+
+pub const AT_FDCWD: isize = -100;
+pub const AT_REMOVEDIR: usize = 0x200;
+
+pub const F_DUPFD: usize = 0;
+pub const F_GETFD: usize = 1;
+pub const F_SETFD: usize = 2;
+pub const F_GETFL: usize = 3;
+pub const F_SETFL: usize = 4;
+pub const F_DUPFD_CLOEXEC: usize = 1030;
+pub const FD_CLOEXEC: usize = 1;
+
+pub const SEEK_SET: usize = 0;
+pub const SEEK_CUR: usize = 1;
+pub const SEEK_END: usize = 2;
+
+// 读写时内核缓冲区的最大长度，用户缓冲区可能比内核堆还大
+const CHUNK_SIZE: usize = PAGE_SIZE;
+
+pub const SYS_GETCWD: usize = 17;
+pub const SYS_DUP: usize = 23;
+pub const SYS_DUP3: usize = 24;
+pub const SYS_FCNTL: usize = 25;
+pub const SYS_IOCTL: usize = 29;
+pub const SYS_MKDIRAT: usize = 34;
+pub const SYS_UNLINKAT: usize = 35;
+pub const SYS_LINKAT: usize = 37;
+pub const SYS_RENAMEAT: usize = 38;
+pub const SYS_UMOUNT2: usize = 39;
+pub const SYS_MOUNT: usize = 40;
+pub const SYS_CHDIR: usize = 49;
+pub const SYS_FACCESSAT: usize = 48;
+pub const SYS_FCHDIR: usize = 50;
+pub const SYS_OPENAT: usize = 56;
+pub const SYS_CLOSE: usize = 57;
+pub const SYS_PIPE2: usize = 59;
+pub const SYS_GETDENTS64: usize = 61;
+pub const SYS_LSEEK: usize = 62;
+pub const SYS_READ: usize = 63;
+pub const SYS_WRITE: usize = 64;
+pub const SYS_READV: usize = 65;
+pub const SYS_WRITEV: usize = 66;
+pub const SYS_PREAD64: usize = 67;
+pub const SYS_PWRITE64: usize = 68;
+pub const SYS_READLINKAT: usize = 78;
+pub const SYS_NEWFSTATAT: usize = 79;
+pub const SYS_FSTAT: usize = 80;
+pub const SYS_SYNC: usize = 81;
+pub const SYS_EXIT: usize = 93;
+pub const SYS_EXIT_GROUP: usize = 94;
+pub const SYS_SET_TID_ADDRESS: usize = 96;
+pub const SYS_SET_ROBUST_LIST: usize = 99;
+pub const SYS_NANOSLEEP: usize = 101;
+pub const SYS_CLOCK_GETTIME: usize = 113;
+pub const SYS_SCHED_YIELD: usize = 124;
+pub const SYS_KILL: usize = 129;
+pub const SYS_RT_SIGACTION: usize = 134;
+pub const SYS_RT_SIGPROCMASK: usize = 135;
+pub const SYS_SETPGID: usize = 154;
+pub const SYS_GETPGID: usize = 155;
+pub const SYS_GETSID: usize = 156;
+pub const SYS_SETSID: usize = 157;
+pub const SYS_UNAME: usize = 160;
+pub const SYS_GETPID: usize = 172;
+pub const SYS_GETPPID: usize = 173;
+pub const SYS_GETUID: usize = 174;
+pub const SYS_GETEUID: usize = 175;
+pub const SYS_GETGID: usize = 176;
+pub const SYS_GETEGID: usize = 177;
+pub const SYS_GETTID: usize = 178;
+pub const SYS_BRK: usize = 214;
+pub const SYS_MUNMAP: usize = 215;
+pub const SYS_CLONE: usize = 220;
+pub const SYS_EXEC: usize = 221;
+pub const SYS_MMAP: usize = 222;
+pub const SYS_MPROTECT: usize = 226;
+pub const SYS_WAIT4: usize = 260;
+
+struct Syscall {
+    id: usize,
+    name: &'static str,
+    args: &'static [Arg],
+    handler: fn(&mut SyscallArgs) -> SysResult,
+}
+
+macro_rules! syscall_table {
+    ($($id:ident $name:literal ($($arg:ident),*) => $handler:expr,)*) => {
+        &[$(Syscall {
+            id: $id,
+            name: $name,
+            args: &[$(Arg::$arg),*],
+            handler: $handler,
+        },)*]
+    };
+}
+
+// 系统调用号、名字、跟踪时参数的显示方式与处理函数
+static SYSCALLS: &[Syscall] = syscall_table! {
+    SYS_GETCWD "getcwd" (Hex, Int) => |a| sys_getcwd(a.mut_ptr(0), a.usize(1)),
+    SYS_DUP "dup" (Int) => |a| sys_dup(a.usize(0)),
+    SYS_DUP3 "dup3" (Int, Int, Oct) => |a| sys_dup3(a.usize(0), a.usize(1), a.usize(2)),
+    SYS_FCNTL "fcntl" (Int, Int, Hex) => |a| sys_fcntl(a.usize(0), a.usize(1), a.usize(2)),
+    SYS_IOCTL "ioctl" (Int, Hex, Hex) => |a| sys_ioctl(a.usize(0), a.usize(1), a.usize(2)),
+    SYS_MKDIRAT "mkdirat" (Int, Str, Oct) => |a| sys_mkdirat(a.usize(0), a.ptr(1), a.u32(2)),
+    SYS_UNLINKAT "unlinkat" (Int, Str, Hex) => |a| sys_unlinkat(a.usize(0), a.ptr(1), a.usize(2)),
+    // AT_SYMLINK_FOLLOW 不需要支持，flags 被忽略
+    SYS_LINKAT "linkat" (Int, Str, Int, Str, Hex) => |a| {
+        sys_linkat(a.usize(0), a.ptr(1), a.usize(2), a.ptr(3))
+    },
+    SYS_RENAMEAT "renameat" (Int, Str, Int, Str) => |a| {
+        sys_renameat(a.usize(0), a.ptr(1), a.usize(2), a.ptr(3))
+    },
+    SYS_UMOUNT2 "umount2" (Str, Hex) => |a| sys_umount2(a.ptr(0)),
+    SYS_MOUNT "mount" (Str, Str, Str, Hex, Hex) => |a| sys_mount(a.ptr(0), a.ptr(1), a.ptr(2)),
+    SYS_CHDIR "chdir" (Str) => |a| sys_chdir(a.ptr(0)),
+    SYS_FACCESSAT "faccessat" (Int, Str, Oct, Hex) => |a| sys_faccessat(a.usize(0), a.ptr(1)),
+    SYS_FCHDIR "fchdir" (Int) => |a| sys_fchdir(a.usize(0)),
+    SYS_OPENAT "openat" (Int, Str, Oct, Oct) => |a| {
+        sys_openat(a.usize(0), a.ptr(1), a.i32(2), a.u32(3))
+    },
+    SYS_CLOSE "close" (Int) => |a| sys_close(a.usize(0)),
+    SYS_PIPE2 "pipe2" (Hex, Oct) => |a| sys_pipe(a.mut_ptr(0), a.usize(1)),
+    SYS_GETDENTS64 "getdents64" (Int, Hex, Int) => |a| {
+        sys_getdents64(a.usize(0), a.mut_ptr(1), a.usize(2))
+    },
+    SYS_LSEEK "lseek" (Int, Int, Int) => |a| sys_lseek(a.usize(0), a.isize(1), a.usize(2)),
+    SYS_READ "read" (Int, Hex, Int) => |a| sys_read(a.usize(0), a.mut_ptr(1), a.usize(2)),
+    SYS_WRITE "write" (Int, Hex, Int) => |a| sys_write(a.usize(0), a.ptr(1), a.usize(2)),
+    SYS_READV "readv" (Int, Hex, Int) => |a| sys_readv(a.usize(0), a.ptr(1), a.usize(2)),
+    SYS_WRITEV "writev" (Int, Hex, Int) => |a| sys_writev(a.usize(0), a.ptr(1), a.usize(2)),
+    SYS_PREAD64 "pread64" (Int, Hex, Int, Int) => |a| {
+        sys_pread(a.usize(0), a.mut_ptr(1), a.usize(2), a.usize(3))
+    },
+    SYS_PWRITE64 "pwrite64" (Int, Hex, Int, Int) => |a| {
+        sys_pwrite(a.usize(0), a.ptr(1), a.usize(2), a.usize(3))
+    },
+    SYS_READLINKAT "readlinkat" (Int, Str, Hex, Int) => |a| {
+        sys_readlinkat(a.usize(0), a.ptr(1), a.mut_ptr(2), a.usize(3))
+    },
+    SYS_NEWFSTATAT "newfstatat" (Int, Str, Hex, Hex) => |a| {
+        sys_fstatat(a.usize(0), a.ptr(1), a.mut_ptr(2))
+    },
+    SYS_FSTAT "fstat" (Int, Hex) => |a| sys_fstat(a.usize(0), a.mut_ptr(1)),
+    SYS_SYNC "sync" () => |_| sys_sync(),
+    SYS_EXIT "exit" (Int) => |a| {
+        sys_exit(a.usize(0));
+        Ok(0)
+    },
+    SYS_EXIT_GROUP "exit_group" (Int) => |a| {
+        sys_exit(a.usize(0));
+        Ok(0)
+    },
+    SYS_SET_TID_ADDRESS "set_tid_address" (Hex) => |a| sys_set_tid_address(a.usize(0)),
+    SYS_SET_ROBUST_LIST "set_robust_list" (Hex, Int) => |_| sys_ignored(),
+    SYS_NANOSLEEP "nanosleep" (Hex, Hex) => |a| sys_nanosleep(a.ptr(0), a.mut_ptr(1)),
+    SYS_CLOCK_GETTIME "clock_gettime" (Int, Hex) => |a| sys_clock_gettime(a.usize(0), a.mut_ptr(1)),
+    SYS_SCHED_YIELD "sched_yield" () => |_| sys_sched_yield(),
+    SYS_KILL "kill" (Int, Int) => |a| sys_kill(a.isize(0), a.usize(1)),
+    SYS_RT_SIGACTION "rt_sigaction" (Int, Hex, Hex, Int) => |a| {
+        sys_rt_sigaction(a.usize(0), a.ptr(1), a.mut_ptr(2))
+    },
+    SYS_RT_SIGPROCMASK "rt_sigprocmask" (Int, Hex, Hex, Int) => |_| sys_ignored(),
+    SYS_SETPGID "setpgid" (Int, Int) => |a| sys_setpgid(a.usize(0), a.isize(1)),
+    SYS_GETPGID "getpgid" (Int) => |a| sys_getpgid(a.usize(0)),
+    SYS_GETSID "getsid" (Int) => |a| sys_getsid(a.usize(0)),
+    SYS_SETSID "setsid" () => |_| sys_setsid(),
+    SYS_UNAME "uname" (Hex) => |a| sys_uname(a.mut_ptr(0)),
+    SYS_GETPID "getpid" () => |_| sys_getpid(),
+    SYS_GETPPID "getppid" () => |_| sys_getppid(),
+    SYS_GETUID "getuid" () => |_| sys_getuid(),
+    SYS_GETEUID "geteuid" () => |_| sys_getuid(),
+    SYS_GETGID "getgid" () => |_| sys_getuid(),
+    SYS_GETEGID "getegid" () => |_| sys_getuid(),
+    SYS_GETTID "gettid" () => |_| sys_gettid(),
+    SYS_BRK "brk" (Hex) => |a| sys_brk(a.usize(0)),
+    SYS_MUNMAP "munmap" (Hex, Int) => |a| sys_munmap(a.usize(0), a.usize(1)),
+    SYS_CLONE "clone" (Hex, Hex, Hex, Hex, Hex) => |a| {
+        sys_clone(a.usize(0), a.usize(1), a.trap_frame())
+    },
+    SYS_EXEC "execve" (Str, Hex, Hex) => |a| {
+        sys_exec(a.ptr(0), a.ptr(1), a.ptr(2), a.trap_frame())
+    },
+    SYS_MMAP "mmap" (Hex, Int, Hex, Hex, Int, Hex) => |a| {
+        sys_mmap(a.usize(0), a.usize(1), a.usize(2), a.usize(3), a.usize(4), a.usize(5))
+    },
+    SYS_MPROTECT "mprotect" (Hex, Int, Hex) => |a| sys_mprotect(a.usize(0), a.usize(1), a.usize(2)),
+    SYS_WAIT4 "wait4" (Int, Hex, Hex, Hex) => |a| sys_wait4(a.isize(0), a.mut_ptr(1), a.usize(2)),
+};
+
+// 成功时返回非负值，失败时返回 -errno
+pub fn syscall(id: usize, args: [usize; 6], tf: &mut TrapFrame) -> isize {
+    let mut args = SyscallArgs::new(args, tf);
+    let trace = process::current_thread_mut().trace;
+    let result = match SYSCALLS.iter().find(|call| call.id == id) {
+        Some(call) => {
+            let line = if trace {
+                Some(format!("{}({})", call.name, args.format(call.args)))
+            } else {
+                None
+            };
+            // exit 与 exit_group 不会返回，先输出
+            if let (Some(line), SYS_EXIT) | (Some(line), SYS_EXIT_GROUP) = (line.as_ref(), id) {
+                println!("[{}] {} = ?", process::current_tid(), line);
+            }
+            let result = (call.handler)(&mut args);
+            if let Some(line) = line {
+                trace_result(&line, &result);
+            }
+            result
+        }
+        None => {
+            warn!("unknown syscall id {}", id);
+            if trace {
+                trace_result(&format!("syscall_{}(...)", id), &Err(SysError::ENOSYS));
+            }
+            Err(SysError::ENOSYS)
+        }
+    };
+    match result {
+        Ok(ret) => ret as isize,
+        Err(e) => -(e as isize),
+    }
+}
+
+fn trace_result(line: &str, result: &SysResult) {
+    match result {
+        Ok(ret) => println!("[{}] {} = {}", process::current_tid(), line, ret),
+        Err(e) => println!(
+            "[{}] {} = -{} {:?}",
+            process::current_tid(),
+            line,
+            *e as isize,
+            e
+        ),
+    }
+}
+
+// 相对路径的起点：AT_FDCWD 表示当前目录，否则是 dirfd 打开的目录
+fn dirfd_inode(dirfd: usize) -> Result<Arc<dyn INode>, SysError> {
+    if dirfd as isize == AT_FDCWD {
+        return Ok(process::current_thread_mut().cwd());
+    }
+    let inode = file_of(dirfd)?
+        .lock()
+        .inode
+        .clone()
+        .ok_or(SysError::EBADF)?;
+    if inode.metadata()?.type_ != FileType::Dir {
+        return Err(SysError::ENOTDIR);
+    }
+    Ok(inode)
+}
+
+// 成功时返回写入的长度，包括结尾的 0
+fn sys_getcwd(base: *mut u8, len: usize) -> SysResult {
+    let path = path_of(&process::current_thread_mut().cwd())?;
+    if path.len() + 1 > len {
+        return Err(SysError::ERANGE);
+    }
+    let mut buf = path.into_bytes();
+    buf.push(0);
+    copy_to_user(base as usize, &buf)?;
+    Ok(buf.len())
+}
+
+fn change_cwd(inode: Arc<dyn INode>) -> SysResult {
+    if inode.metadata()?.type_ != FileType::Dir {
+        return Err(SysError::ENOTDIR);
+    }
+    process::current_thread_mut().cwd = Some(inode);
+    Ok(0)
+}
+
+fn sys_chdir(path: *const u8) -> SysResult {
+    let cwd = process::current_thread_mut().cwd();
+    change_cwd(lookup_at(&cwd, &from_cstr(path)?)?)
+}
+
+fn sys_fchdir(fd: usize) -> SysResult {
+    change_cwd(file_of(fd)?.lock().inode.clone().ok_or(SysError::EBADF)?)
+}
+
+fn sys_openat(dirfd: usize, path: *const u8, flags: i32, mode: u32) -> SysResult {
+    let base = dirfd_inode(dirfd)?;
+    let mut file = File::default();
+    file.open_file(&base, &from_cstr(path)?, flags, mode)?;
+    process::current_thread_mut()
+        .files
+        .lock()
+        .add(Arc::new(Mutex::new(file)), flags & O_CLOEXEC != 0)
+        .ok_or(SysError::EMFILE)
+}
+
+// flags 中只支持 O_CLOEXEC
+fn sys_pipe(fds: *mut i32, flags: usize) -> SysResult {
+    let (read_end, write_end) = Pipe::new();
+    let mut files = process::current_thread_mut().files.lock();
+    // 两个文件描述符都分配成功才会生效
+    let mut fd = [0; 2];
+    for (i, (end, writable)) in [(read_end, false), (write_end, true)].iter().enumerate() {
+        let mut file = File::default();
+        file.set_fdtype(FileDescriptorType::FD_PIPE);
+        file.set_readable(!writable);
+        file.set_writable(*writable);
+        file.inode = Some(end.clone());
+        match files.add(Arc::new(Mutex::new(file)), flags & O_CLOEXEC as usize != 0) {
+            Some(new_fd) => fd[i] = new_fd,
+            None => {
+                if i == 1 {
+                    files.remove(fd[0]);
+                }
+                return Err(SysError::EMFILE);
+            }
+        }
+    }
+    let fd = [fd[0] as i32, fd[1] as i32];
+    if let Err(e) = write_user(fds as usize, &fd) {
+        files.remove(fd[0] as usize);
+        files.remove(fd[1] as usize);
+        return Err(e);
+    }
+    Ok(0)
+}
+
+fn sys_dup(fd: usize) -> SysResult {
+    sys_fcntl(fd, F_DUPFD, 0)
+}
+
+fn sys_dup3(old_fd: usize, new_fd: usize, flags: usize) -> SysResult {
+    let file = file_of(old_fd)?;
+    // 只支持 O_CLOEXEC
+    if old_fd == new_fd || flags & !(O_CLOEXEC as usize) != 0 {
+        return Err(SysError::EINVAL);
+    }
+    let mut files = process::current_thread_mut().files.lock();
+    files
+        .set(new_fd, file, flags & O_CLOEXEC as usize != 0)
+        .ok_or(SysError::EBADF)?;
+    Ok(new_fd)
+}
+
+fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> SysResult {
+    let file = file_of(fd)?;
+    let mut files = process::current_thread_mut().files.lock();
+    match cmd {
+        F_DUPFD | F_DUPFD_CLOEXEC => files
+            .add_from(arg, file, cmd == F_DUPFD_CLOEXEC)
+            .ok_or(SysError::EMFILE),
+        F_GETFD => match files.cloexec(fd) {
+            Some(true) => Ok(FD_CLOEXEC),
+            _ => Ok(0),
+        },
+        F_SETFD => {
+            files.set_cloexec(fd, arg & FD_CLOEXEC != 0);
+            Ok(0)
+        }
+        F_GETFL => Ok(file.lock().get_flags() as usize),
+        // 访问模式不能修改，目前只支持 O_APPEND
+        F_SETFL => {
+            file.lock().set_append(arg & O_APPEND as usize != 0);
+            Ok(0)
+        }
+        _ => Err(SysError::EINVAL),
+    }
+}
+
+fn sys_mkdirat(dirfd: usize, path: *const u8, mode: u32) -> SysResult {
+    let base = dirfd_inode(dirfd)?;
+    let path = from_cstr(path)?;
+    let (dir, name) = lookup_parent(&base, &path)?;
+    dir.create(name, FileType::Dir, mode)?;
+    Ok(0)
+}
+
+fn sys_unlinkat(dirfd: usize, path: *const u8, flags: usize) -> SysResult {
+    let base = dirfd_inode(dirfd)?;
+    let path = from_cstr(path)?;
+    let (dir, name) = lookup_parent(&base, &path)?;
+    let is_dir = dir.find(name)?.metadata()?.type_ == FileType::Dir;
+    match (is_dir, flags & AT_REMOVEDIR != 0) {
+        (true, false) => Err(SysError::EISDIR),
+        (false, true) => Err(SysError::ENOTDIR),
+        _ => {
+            dir.unlink(name)?;
+            Ok(0)
+        }
+    }
+}
+
+fn sys_linkat(
+    olddirfd: usize,
+    oldpath: *const u8,
+    newdirfd: usize,
+    newpath: *const u8,
+) -> SysResult {
+    let inode = lookup_at(&dirfd_inode(olddirfd)?, &from_cstr(oldpath)?)?;
+    let newpath = from_cstr(newpath)?;
+    let (dir, name) = lookup_parent(&dirfd_inode(newdirfd)?, &newpath)?;
+    dir.link(name, &inode)?;
+    Ok(0)
+}
+
+fn sys_renameat(
+    olddirfd: usize,
+    oldpath: *const u8,
+    newdirfd: usize,
+    newpath: *const u8,
+) -> SysResult {
+    let (oldpath, newpath) = (from_cstr(oldpath)?, from_cstr(newpath)?);
+    let (old_dir, old_name) = lookup_parent(&dirfd_inode(olddirfd)?, &oldpath)?;
+    let (new_dir, new_name) = lookup_parent(&dirfd_inode(newdirfd)?, &newpath)?;
+    old_dir.move_(old_name, &new_dir, new_name)?;
+    Ok(0)
+}
+
+// 目前只支持挂载内存文件系统，source 被忽略
+fn sys_mount(_source: *const u8, target: *const u8, fstype: *const u8) -> SysResult {
+    let fs: Arc<dyn FileSystem> = match from_cstr(fstype)?.as_str() {
+        "tmpfs" | "ramfs" => RamFs::new(),
+        _ => return Err(SysError::ENODEV),
+    };
+    mount::mount(fs, &from_cstr(target)?)?;
+    Ok(0)
+}
+
+fn sys_umount2(target: *const u8) -> SysResult {
+    mount::umount(&from_cstr(target)?)?;
+    Ok(0)
+}
+
+// 与 Linux 相同，写回失败不会报告给调用者
+fn sys_sync() -> SysResult {
+    if let Err(e) = mount::sync() {
+        warn!("failed to sync file systems: {:?}", e);
+    }
+    Ok(0)
+}
+
+fn sys_close(fd: usize) -> SysResult {
+    process::current_thread_mut()
+        .files
+        .lock()
+        .remove(fd)
+        .ok_or(SysError::EBADF)?;
+    Ok(0)
+}
+
+fn sys_exit(code: usize) {
+    process::exit(code);
+}
+
+fn sys_read(fd: usize, base: *mut u8, len: usize) -> SysResult {
+    let file = file_of(fd)?;
+    // 读取设备时可能会睡眠，不能一直持有文件的锁
+    let (fdtype, inode, offset) = {
+        let file = file.lock();
+        if !file.get_readable() {
+            return Err(SysError::EBADF);
+        }
+        (
+            file.get_fdtype(),
+            file.inode.clone().ok_or(SysError::EBADF)?,
+            file.get_offset(),
+        )
+    };
+    // 先检查地址，避免读出的数据因无法复制而丢失
+    access_ok(base as usize, len, true)?;
+    let base = base as usize;
+    match fdtype {
+        FileDescriptorType::FD_INODE => {
+            let s = read_chunked(base, len, |pos, buf| Ok(inode.read_at(offset + pos, buf)?))?;
+            file.lock().set_offset(offset + s);
+            Ok(s)
+        }
+        // 设备与管道可能会阻塞，只读一块，读到的数据可能比 len 少
+        FileDescriptorType::FD_DEVICE => read_chunked(base, len.min(CHUNK_SIZE), |_, buf| {
+            Ok(inode.read_at(0, buf)?)
+        }),
+        FileDescriptorType::FD_PIPE => {
+            let pipe = inode.downcast_ref::<PipeEnd>().unwrap();
+            read_chunked(base, len.min(CHUNK_SIZE), |_, buf| Ok(pipe.read(buf)?))
+        }
+        FileDescriptorType::FD_NONE => Err(SysError::EBADF),
+    }
+}
+
+fn sys_write(fd: usize, base: *const u8, len: usize) -> SysResult {
+    let file = file_of(fd)?;
+    let (fdtype, inode, offset) = {
+        let file = file.lock();
+        if !file.get_writable() {
+            return Err(SysError::EBADF);
+        }
+        let inode = file.inode.clone().ok_or(SysError::EBADF)?;
+        // O_APPEND 时每次写入前都移动到文件末尾
+        let offset = if file.get_append() {
+            inode.metadata()?.size
+        } else {
+            file.get_offset()
+        };
+        (file.get_fdtype(), inode, offset)
+    };
+    access_ok(base as usize, len, false)?;
+    let base = base as usize;
+    match fdtype {
+        FileDescriptorType::FD_INODE => {
+            let s = write_chunked(base, len, |pos, buf| Ok(inode.write_at(offset + pos, buf)?))?;
+            file.lock().set_offset(offset + s);
+            Ok(s)
+        }
+        FileDescriptorType::FD_DEVICE => {
+            write_chunked(base, len, |_, buf| Ok(inode.write_at(0, buf)?))
+        }
+        // 读端已全部关闭
+        FileDescriptorType::FD_PIPE => {
+            let pipe = inode.downcast_ref::<PipeEnd>().unwrap();
+            write_chunked(base, len, |_, buf| pipe.write(buf)?.ok_or(SysError::EPIPE))
+        }
+        FileDescriptorType::FD_NONE => Err(SysError::EBADF),
+    }
+}
+
+// 每次最多读 CHUNK_SIZE 字节并复制到用户内存，read 的参数是已读的长度与本次的缓冲区；
+// 读到的比请求的少时停止，已经读到数据后出错时返回已读的长度
+fn read_chunked(
+    base: usize,
+    len: usize,
+    mut read: impl FnMut(usize, &mut [u8]) -> Result<usize, SysError>,
+) -> SysResult {
+    let mut buf = vec![0u8; len.min(CHUNK_SIZE)];
+    let mut done = 0;
+    while done < len {
+        let chunk = &mut buf[..(len - done).min(CHUNK_SIZE)];
+        match read(done, chunk).and_then(|s| copy_to_user(base + done, &chunk[..s]).map(|_| s)) {
+            Ok(s) => {
+                done += s;
+                if s < chunk.len() {
+                    break;
+                }
+            }
+            Err(e) if done == 0 => return Err(e),
+            Err(_) => break,
+        }
+    }
+    Ok(done)
+}
+
+// 与 read_chunked 相同，方向相反
+fn write_chunked(
+    base: usize,
+    len: usize,
+    mut write: impl FnMut(usize, &[u8]) -> Result<usize, SysError>,
+) -> SysResult {
+    let mut buf = vec![0u8; len.min(CHUNK_SIZE)];
+    let mut done = 0;
+    while done < len {
+        let chunk = &mut buf[..(len - done).min(CHUNK_SIZE)];
+        match copy_from_user(base + done, chunk).and_then(|_| write(done, chunk)) {
+            Ok(s) => {
+                done += s;
+                if s < chunk.len() {
+                    break;
+                }
+            }
+            Err(e) if done == 0 => return Err(e),
+            Err(_) => break,
+        }
+    }
+    Ok(done)
+}
+
+// 依次读写每个缓冲区，某次读到的长度不足时停止；出错时返回已完成的长度
+fn sys_readv(fd: usize, iov: *const [usize; 2], iovcnt: usize) -> SysResult {
+    vectored(iov, iovcnt, |base, len| sys_read(fd, base as *mut u8, len))
+}
+
+fn sys_writev(fd: usize, iov: *const [usize; 2], iovcnt: usize) -> SysResult {
+    vectored(iov, iovcnt, |base, len| {
+        sys_write(fd, base as *const u8, len)
+    })
+}
+
+// struct iovec { iov_base: usize, iov_len: usize }
+fn vectored(
+    iov: *const [usize; 2],
+    iovcnt: usize,
+    mut f: impl FnMut(usize, usize) -> SysResult,
+) -> SysResult {
+    if iovcnt > IOV_MAX {
+        return Err(SysError::EINVAL);
+    }
+    let mut total = 0;
+    for i in 0..iovcnt {
+        let [base, len]: [usize; 2] = read_user(iov as usize + i * 16)?;
+        match f(base, len) {
+            Ok(size) => {
+                total += size;
+                if size < len {
+                    break;
+                }
+            }
+            Err(e) if total == 0 => return Err(e),
+            Err(_) => break,
+        }
+    }
+    Ok(total)
+}
+
+fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SysResult {
+    let inode = file_of(fd)?.lock().inode.clone().ok_or(SysError::EBADF)?;
+    if !tty::is_tty(&inode) {
+        return Err(SysError::ENOTTY);
+    }
+    tty::ioctl(cmd, arg)
+}
+
+// 只有 root 用户，文件存在即可访问
+fn sys_faccessat(dirfd: usize, path: *const u8) -> SysResult {
+    lookup_at(&dirfd_inode(dirfd)?, &from_cstr(path)?)?;
+    Ok(0)
+}
+
+fn file_of(fd: usize) -> Result<Arc<Mutex<File>>, SysError> {
+    process::current_thread_mut()
+        .files
+        .lock()
+        .get(fd)
+        .ok_or(SysError::EBADF)
+}
+
+// fd 对应的普通文件或目录，管道与设备不能定位
+fn seekable_inode(fd: usize) -> Result<Arc<dyn INode>, SysError> {
+    let file = file_of(fd)?;
+    let file = file.lock();
+    match file.get_fdtype() {
+        FileDescriptorType::FD_INODE => file.inode.clone().ok_or(SysError::EBADF),
+        _ => Err(SysError::ESPIPE),
+    }
+}
+
+fn sys_fstat(fd: usize, stat: *mut Stat) -> SysResult {
+    let inode = file_of(fd)?.lock().inode.clone().ok_or(SysError::EBADF)?;
+    write_user(stat as usize, &Stat::from(inode.metadata()?))?;
+    Ok(0)
+}
+
+// 符号链接目前总是被跟随，flags 被忽略
+fn sys_fstatat(dirfd: usize, path: *const u8, stat: *mut Stat) -> SysResult {
+    let inode = lookup_at(&dirfd_inode(dirfd)?, &from_cstr(path)?)?;
+    write_user(stat as usize, &Stat::from(inode.metadata()?))?;
+    Ok(0)
+}
+
+// 不是符号链接时返回 EINVAL，内容超过 len 时被截断
+fn sys_readlinkat(dirfd: usize, path: *const u8, base: *mut u8, len: usize) -> SysResult {
+    let inode = lookup_at(&dirfd_inode(dirfd)?, &from_cstr(path)?)?;
+    if inode.metadata()?.type_ != FileType::SymLink {
+        return Err(SysError::EINVAL);
+    }
+    access_ok(base as usize, len, true)?;
+    let mut buf = vec![0u8; len.min(PATH_MAX)];
+    let s = inode.read_at(0, &mut buf)?;
+    copy_to_user(base as usize, &buf[..s])?;
+    Ok(s)
+}
+
+// 目录文件的 offset 是下一个要读取的目录项的编号
+fn sys_getdents64(fd: usize, base: *mut u8, len: usize) -> SysResult {
+    let file = file_of(fd)?;
+    let mut file = file.lock();
+    let inode = file.inode.clone().ok_or(SysError::EBADF)?;
+    if inode.metadata()?.type_ != FileType::Dir {
+        return Err(SysError::ENOTDIR);
+    }
+    access_ok(base as usize, len, true)?;
+    let mut buf = Vec::new();
+    // struct linux_dirent64 { d_ino: u64, d_off: i64, d_reclen: u16, d_type: u8, d_name: [u8] }
+    const HEADER: usize = 19;
+    let mut written = 0;
+    loop {
+        let id = file.get_offset();
+        let name = match inode.get_entry(id) {
+            Ok(name) => name,
+            Err(_) => break,
+        };
+        let reclen = (HEADER + name.len() + 1 + 7) & !7;
+        if written + reclen > len {
+            if written == 0 {
+                return Err(SysError::EINVAL);
+            }
+            break;
+        }
+        let (ino, type_) = match inode.find(&name).and_then(|child| child.metadata()) {
+            Ok(metadata) => (metadata.inode, dirent_type(metadata.type_)),
+            Err(_) => (0, 0),
+        };
+        buf.resize(written + reclen, 0);
+        let entry = &mut buf[written..];
+        entry[0..8].copy_from_slice(&(ino as u64).to_le_bytes());
+        entry[8..16].copy_from_slice(&((id + 1) as i64).to_le_bytes());
+        entry[16..18].copy_from_slice(&(reclen as u16).to_le_bytes());
+        entry[18] = type_;
+        entry[HEADER..HEADER + name.len()].copy_from_slice(name.as_bytes());
+        written += reclen;
+        file.set_offset(id + 1);
+    }
+    copy_to_user(base as usize, &buf)?;
+    Ok(written)
+}
+
+fn sys_lseek(fd: usize, offset: isize, whence: usize) -> SysResult {
+    let inode = seekable_inode(fd)?;
+    let file = file_of(fd)?;
+    let mut file = file.lock();
+    let base = match whence {
+        SEEK_SET => 0,
+        SEEK_CUR => file.get_offset() as isize,
+        SEEK_END => inode.metadata()?.size as isize,
+        _ => return Err(SysError::EINVAL),
+    };
+    let offset = base + offset;
+    if offset < 0 {
+        return Err(SysError::EINVAL);
+    }
+    file.set_offset(offset as usize);
+    Ok(offset as usize)
+}
+
+// pread 与 pwrite 使用给定的 offset，不修改文件的 offset
+fn sys_pread(fd: usize, base: *mut u8, len: usize, offset: usize) -> SysResult {
+    if !file_of(fd)?.lock().get_readable() {
+        return Err(SysError::EBADF);
+    }
+    access_ok(base as usize, len, true)?;
+    let inode = seekable_inode(fd)?;
+    read_chunked(base as usize, len, |pos, buf| {
+        Ok(inode.read_at(offset + pos, buf)?)
+    })
+}
+
+fn sys_pwrite(fd: usize, base: *const u8, len: usize, offset: usize) -> SysResult {
+    if !file_of(fd)?.lock().get_writable() {
+        return Err(SysError::EBADF);
+    }
+    access_ok(base as usize, len, false)?;
+    let inode = seekable_inode(fd)?;
+    write_chunked(base as usize, len, |pos, buf| {
+        Ok(inode.write_at(offset + pos, buf)?)
+    })
+}
+
+// 从用户内存中复制路径等字符串
+fn from_cstr(s: *const u8) -> Result<String, SysError> {
+    strncpy_from_user(s as usize, PATH_MAX - 1)
+}
+
+// 成功时不返回调用者，而是从新程序的入口回到用户态
+// argv 为空时以 path 作为唯一的参数
+fn sys_exec(
+    path: *const u8,
+    argv: *const usize,
+    envp: *const usize,
+    tf: &mut TrapFrame,
+) -> SysResult {
+    let path = from_cstr(path)?;
+    let mut args = read_strings(argv)?;
+    if args.is_empty() {
+        args.push(path.clone());
+    }
+    let envs = read_strings(envp)?;
+    process::exec(&path, &args, &envs, tf)?;
+    Ok(0)
+}
+
+// 以 NULL 结尾的字符串指针数组，ptr 为 NULL 时视为空数组
+fn read_strings(ptr: *const usize) -> Result<Vec<String>, SysError> {
+    let mut strings = Vec::new();
+    if ptr.is_null() {
+        return Ok(strings);
+    }
+    loop {
+        let s: usize = read_user(ptr as usize + strings.len() * 8)?;
+        if s == 0 {
+            return Ok(strings);
+        }
+        if strings.len() == ARG_MAX {
+            return Err(SysError::E2BIG);
+        }
+        strings.push(strncpy_from_user(s, PATH_MAX - 1)?);
+    }
+}
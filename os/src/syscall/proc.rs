@@ -0,0 +1,207 @@
+use super::errno::{SysError, SysResult};
+use crate::context::TrapFrame;
+use crate::interrupt::{disable_and_store, restore};
+use crate::memory::uaccess::{read_user, write_user};
+use crate::process::{self, group_in_session, signal::*, structs::Status, Tid};
+use alloc::vec::Vec;
+
+pub const WNOHANG: usize = 1;
+pub const WUNTRACED: usize = 2;
+
+pub const CSIGNAL: usize = 0xff;
+pub const CLONE_VM: usize = 0x100;
+pub const CLONE_VFORK: usize = 0x4000;
+
+// riscv64 上的 struct sigaction，没有 sa_restorer
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SigAction {
+    handler: usize,
+    flags: usize,
+    mask: u64,
+}
+
+// 只支持 fork 与 vfork：CLONE_VM 必须与 CLONE_VFORK 一起使用，子线程结束时总是通知父线程，
+// CSIGNAL 中的信号被忽略
+pub fn sys_clone(flags: usize, stack: usize, tf: &mut TrapFrame) -> SysResult {
+    if flags & !(CSIGNAL | CLONE_VM | CLONE_VFORK) != 0 {
+        return Err(SysError::EINVAL);
+    }
+    if flags & CLONE_VM != 0 && flags & CLONE_VFORK == 0 {
+        return Err(SysError::EINVAL);
+    }
+    process::clone(tf, flags & CLONE_VFORK != 0, stack)
+}
+
+// 只支持等待某个子线程 (pid > 0) 或任意子线程 (pid == -1)
+// 子线程被暂停时，设置了 WUNTRACED 才会返回
+pub fn sys_wait4(pid: isize, wstatus: *mut i32, options: usize) -> SysResult {
+    if pid == 0 || pid < -1 {
+        return Err(SysError::EINVAL);
+    }
+    let me = process::current_tid();
+    let matches = |tid: Tid| pid == -1 || tid as isize == pid;
+    loop {
+        // 检查与睡眠之间不能被中断，否则可能错过子线程退出时的唤醒
+        let flags = disable_and_store();
+        let thread = process::current_thread_mut();
+        if let Some(i) = thread.exited.iter().position(|&(tid, _)| matches(tid)) {
+            let (tid, status) = thread.exited.remove(i);
+            process::reap(tid);
+            restore(flags);
+            if !wstatus.is_null() {
+                write_user(wstatus as usize, &(status as i32))?;
+            }
+            return Ok(tid);
+        }
+        let children: Vec<(Tid, Option<usize>)> = process::tids()
+            .into_iter()
+            .filter(|&tid| matches(tid))
+            .filter_map(|tid| {
+                process::with_thread(tid, |status, thread| match status {
+                    _ if thread.wait != Some(me) => None,
+                    Status::Stopped(sig) => Some((tid, Some(*sig))),
+                    _ => Some((tid, None)),
+                })?
+            })
+            .collect();
+        if children.is_empty() {
+            restore(flags);
+            return Err(SysError::ECHILD);
+        }
+        if options & WUNTRACED != 0 {
+            if let Some(&(tid, Some(sig))) = children.iter().find(|(_, stopped)| stopped.is_some())
+            {
+                restore(flags);
+                if !wstatus.is_null() {
+                    write_user(wstatus as usize, &(stopped_status(sig) as i32))?;
+                }
+                return Ok(tid);
+            }
+        }
+        if options & WNOHANG != 0 {
+            restore(flags);
+            return Ok(0);
+        }
+        if process::signal_pending() {
+            restore(flags);
+            return Err(SysError::EINTR);
+        }
+        process::current_thread_mut()
+            .child_changed
+            .wait_restore(flags);
+    }
+}
+
+// pid > 0 发给一个线程，pid == 0 发给调用者所在的进程组，pid < -1 发给进程组 -pid，
+// pid == -1 发给除自己与 init 以外的所有线程；sig 为 0 时只检查目标是否存在
+pub fn sys_kill(pid: isize, sig: usize) -> SysResult {
+    if sig > NSIG {
+        return Err(SysError::EINVAL);
+    }
+    let found = match pid {
+        -1 => {
+            let me = process::current_tid();
+            let targets: Vec<Tid> = process::tids()
+                .into_iter()
+                .filter(|&tid| tid != me && tid != 1)
+                .collect();
+            for &tid in targets.iter() {
+                process::send_signal(tid, sig);
+            }
+            !targets.is_empty()
+        }
+        0 => process::send_signal_to_group(process::current_thread_mut().pgid, sig),
+        pid if pid < 0 => process::send_signal_to_group(-pid as Tid, sig),
+        pid => process::send_signal(pid as Tid, sig),
+    };
+    if found {
+        Ok(0)
+    } else {
+        Err(SysError::ESRCH)
+    }
+}
+
+// 还不能调用用户的信号处理函数，设置处理函数等同于恢复默认动作
+pub fn sys_rt_sigaction(sig: usize, act: *const SigAction, oldact: *mut SigAction) -> SysResult {
+    if sig == 0 || sig > NSIG || !act.is_null() && !can_ignore(sig) {
+        return Err(SysError::EINVAL);
+    }
+    let thread = process::current_thread_mut();
+    if !oldact.is_null() {
+        let old = SigAction {
+            handler: if thread.ignored & bit(sig) != 0 {
+                SIG_IGN
+            } else {
+                SIG_DFL
+            },
+            flags: 0,
+            mask: 0,
+        };
+        write_user(oldact as usize, &old)?;
+    }
+    if !act.is_null() {
+        let act: SigAction = read_user(act as usize)?;
+        if act.handler == SIG_IGN {
+            thread.ignored |= bit(sig);
+            thread.pending &= !bit(sig);
+        } else {
+            thread.ignored &= !bit(sig);
+        }
+    }
+    Ok(0)
+}
+
+// pid 只能是调用者或它的子线程，pgid 为 0 时使用 pid；
+// 只能加入同一会话中已有的进程组，会话首领不能改变进程组
+pub fn sys_setpgid(pid: usize, pgid: isize) -> SysResult {
+    if pgid < 0 {
+        return Err(SysError::EINVAL);
+    }
+    let me = process::current_tid();
+    let pid = if pid == 0 { me } else { pid };
+    let pgid = if pgid == 0 { pid } else { pgid as Tid };
+    let (wait, sid) =
+        process::with_thread(pid, |_, thread| (thread.wait, thread.sid)).ok_or(SysError::ESRCH)?;
+    if pid != me && wait != Some(me) {
+        return Err(SysError::ESRCH);
+    }
+    if sid != process::current_thread_mut().sid || sid == pid {
+        return Err(SysError::EPERM);
+    }
+    if pgid != pid && !group_in_session(pgid, sid) {
+        return Err(SysError::EPERM);
+    }
+    process::with_thread_mut(pid, |thread| thread.pgid = pgid);
+    Ok(0)
+}
+
+pub fn sys_getpgid(pid: usize) -> SysResult {
+    let pid = if pid == 0 {
+        process::current_tid()
+    } else {
+        pid
+    };
+    process::with_thread(pid, |_, thread| thread.pgid).ok_or(SysError::ESRCH)
+}
+
+pub fn sys_getsid(pid: usize) -> SysResult {
+    let pid = if pid == 0 {
+        process::current_tid()
+    } else {
+        pid
+    };
+    process::with_thread(pid, |_, thread| thread.sid).ok_or(SysError::ESRCH)
+}
+
+// 进程组首领不能创建新的会话
+pub fn sys_setsid() -> SysResult {
+    let me = process::current_tid();
+    if group_in_session(me, process::current_thread_mut().sid) {
+        return Err(SysError::EPERM);
+    }
+    let thread = process::current_thread_mut();
+    thread.pgid = me;
+    thread.sid = me;
+    Ok(me)
+}
@@ -1,9 +1,24 @@
+use crate::interrupt::restore;
+use crate::process::{current_tid, wake_up, yield_now, Tid};
 use crate::sbi::set_timer;
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+use lazy_static::*;
 use riscv::register::{sie, time};
+use spin::Mutex;
 
 pub static mut TICKS: usize = 0;
 
 static TIMEBASE: u64 = 100000;
+// QEMU virt 的时钟频率为 10MHz，每 TIMEBASE 个周期产生一次时钟中断
+pub const TICKS_PER_SECOND: usize = 100;
+const CLOCK_FREQ: u64 = 10_000_000;
+
+lazy_static! {
+    // 睡眠中的线程与它们被唤醒的时刻，最早的在堆顶
+    static ref SLEEPERS: Mutex<BinaryHeap<Reverse<(u64, Tid)>>> = Mutex::new(BinaryHeap::new());
+}
+
 pub fn init() {
     unsafe {
         TICKS = 0;
@@ -17,6 +32,32 @@ pub fn clock_set_next_event() {
     set_timer(get_cycle() + TIMEBASE);
 }
 
-fn get_cycle() -> u64 {
+pub fn get_cycle() -> u64 {
     time::read() as u64
 }
+
+// 启动以来经过的纳秒数
+pub fn get_time_ns() -> u64 {
+    get_cycle() * (1_000_000_000 / CLOCK_FREQ)
+}
+
+// 调用者关闭中断后检查等待条件，条件不满足时调用，睡眠到 deadline 纳秒或被信号唤醒，
+// 醒来后以 flags 恢复中断
+pub fn sleep_restore(deadline: u64, flags: usize) {
+    SLEEPERS.lock().push(Reverse((deadline, current_tid())));
+    yield_now();
+    restore(flags);
+}
+
+// 时钟中断时唤醒到期的线程
+pub fn wake_sleepers() {
+    let now = get_time_ns();
+    let mut sleepers = SLEEPERS.lock();
+    while let Some(&Reverse((deadline, tid))) = sleepers.peek() {
+        if deadline > now {
+            break;
+        }
+        sleepers.pop();
+        wake_up(tid);
+    }
+}
@@ -1,4 +1,8 @@
 .equ XLENB, 8
+# 与 consts.rs 中的内核栈布局保持一致：
+# 每个栈槽 1 MiB，高 512 KiB 为栈，低 512 KiB 为保护区域
+.equ KERNEL_STACK_REGION, 0xffffffff80000000
+.equ KERNEL_STACK_SHIFT, 19
 .macro LOAD a1, a2
 	ld \a1, \a2*XLENB(sp)
 .endm
@@ -12,6 +16,26 @@
 	bnez sp, trap_from_user
 trap_from_kernel:
 	csrr sp, sscratch
+	# 若保存 TrapFrame 会落入内核栈的保护区域，则切换到专用栈上报告溢出
+	csrw sscratch, t0
+	li t0, KERNEL_STACK_REGION
+	sub t0, sp, t0
+	addi t0, t0, -36*XLENB
+	srli t0, t0, 30
+	bnez t0, kernel_stack_ok
+	li t0, KERNEL_STACK_REGION
+	sub t0, sp, t0
+	addi t0, t0, -36*XLENB
+	srli t0, t0, KERNEL_STACK_SHIFT
+	andi t0, t0, 1
+	bnez t0, kernel_stack_ok
+	csrr t0, sscratch
+	csrw sscratch, sp
+	la sp, kstack_overflow_top
+	j trap_from_user
+kernel_stack_ok:
+	csrr t0, sscratch
+	csrw sscratch, sp
 trap_from_user:
 	addi sp, sp, -36*XLENB
 	STORE x1, 1
@@ -114,3 +138,11 @@ __alltraps:
 __trapret:
 	RESTORE_ALL
 	sret
+
+	.section .bss.kstack_overflow
+	.align 12
+	.global kstack_overflow
+kstack_overflow:
+	.space 4096 * 4
+	.global kstack_overflow_top
+kstack_overflow_top:
@@ -9,6 +9,18 @@ tests = {
     'lab6': (True, 'stride_test.rs'),
     'lab7': (False, 'mutex_test.rs'),
     'lab8': (True, 'pipe_test.rs'),
+    'lab9': (True, 'dup3_test.rs'),
+    'lab10': (True, 'wait_test.rs'),
+    'lab11': (True, 'mm_test.rs'),
+    'lab12': (True, 'tty_test.rs'),
+    'lab13': (True, 'fs_test.rs'),
+    'lab14': (True, 'fs_test.rs'),
+}
+# 根文件系统的来源：lab13 没有块设备，以 SFS 镜像为下层的 overlay 为根；
+# lab14 同时有 cpio 归档与块设备，cpio 归档解压出的内存文件系统为根
+make_args = {
+    'lab13': ' DISK=',
+    'lab14': ' INITRD=../usr/build/riscv64.cpio',
 }
 if sys.argv[1] == 'clean':
     os.system('rm lab*')
@@ -18,19 +30,15 @@ try:
     user_test, test_file = tests[sys.argv[1]]
     if user_test:
         # user_test
-        # save process/mod.rs
-        os.system('\\cp os/src/process/mod.rs os/src/process/mod_backup.rs')
-        # replace with user test
+        # add user test
         os.system('\\cp test/usr/' + test_file +
                   ' usr/rust/src/bin/' + test_file)
-        s = open('os/src/process/mod.rs').read()
-        s = s.replace('rust/user_shell', 'rust/' +
-                      test_file[:test_file.find('.')])
-        with open('os/src/process/mod.rs', 'w') as f:
-            f.write(s)
-        # try test
+        # try test, the kernel runs it as init program
         c = os.system('make clean')
-        c = os.system('make run > ' + sys.argv[1] + '.result')
+        c = os.system('make run BOOTARGS="init=rust/' +
+                      test_file[:test_file.find('.')] + '"' +
+                      make_args.get(sys.argv[1], '') + ' > ' +
+                      sys.argv[1] + '.result')
         if c == 0:
             print('test successfully')
         else:
@@ -38,9 +46,6 @@ try:
         print('see ' + sys.argv[1] + '.result')
         # remove user test
         os.system('rm usr/rust/src/bin/' + test_file)
-        # backup process/mod.rs
-        os.system('\\cp os/src/process/mod_backup.rs os/src/process/mod.rs')
-        os.system('rm os/src/process/mod_backup.rs')
         # open result file
         if c == 0:
             os.system('cat ' + sys.argv[1] + '.result | less')
@@ -64,4 +69,4 @@ try:
         if c == 0:
             os.system('cat ' + sys.argv[1] + '.result | less')
 except:
-    print('Usage: python3 test.py labX/clean (X={2,3,5,6,7,8,kernel,user})')
+    print('Usage: python3 test.py labX/clean (X={2,3,5,6,7,8,9,10,11,12,13,14,kernel,user})')
@@ -1,19 +1,13 @@
 global_asm!(include_str!("boot/entry64.asm"));
-global_asm!(include_str!("link_user.S"));
 
-use crate::consts::*;
 use crate::memory::{alloc_frame, dealloc_frame};
 
+// a0 = hartid, a1 = 设备树的物理地址，均由 OpenSBI 传入
 #[no_mangle]
-pub extern "C" fn rust_main() -> ! {
-    extern "C" {
-        fn end();
-    }
-    crate::memory::init(
-        ((end as usize - KERNEL_BEGIN_VADDR + KERNEL_BEGIN_PADDR) >> 12) + 1,
-        PHYSICAL_MEMORY_END >> 12,
-    );
+pub extern "C" fn rust_main(_hartid: usize, dtb: usize) -> ! {
+    crate::boot::early_init(dtb);
     crate::interrupt::init();
+    crate::drivers::init();
     crate::fs::init();
     crate::process::init();
     crate::process::spawn(philosopher_using_mutex);
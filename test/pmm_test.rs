@@ -1,18 +1,12 @@
 global_asm!(include_str!("boot/entry64.asm"));
 
-use crate::consts::*;
 use crate::memory::{alloc_frame, dealloc_frame};
 
+// a0 = hartid, a1 = 设备树的物理地址，均由 OpenSBI 传入
 #[no_mangle]
-pub extern "C" fn rust_main() -> ! {
+pub extern "C" fn rust_main(_hartid: usize, dtb: usize) -> ! {
     let FF_grade = FirstFitAllocator_test();
-    extern "C" {
-        fn end();
-    }
-    crate::memory::init(
-        ((end as usize - KERNEL_BEGIN_VADDR + KERNEL_BEGIN_PADDR) >> 12) + 1,
-        PHYSICAL_MEMORY_END >> 12,
-    );
+    crate::boot::early_init(dtb);
     println!("First Fit Allocator: {} / 8", FF_grade);
     crate::sbi::shutdown();
 }
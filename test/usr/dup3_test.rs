@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user;
+
+use core::ptr;
+use core::str;
+use user::errno::Errno;
+use user::io::*;
+use user::syscall::{
+    sys_close, sys_dup3, sys_exec, sys_exit, sys_fcntl, sys_fork, sys_open, sys_read, sys_wait4,
+};
+
+const FILE: &str = "dup3_out\0";
+const PROGRAM: &str = "/rust/hello_world\0";
+
+// 子进程把 fd 复制到标准输出后执行 hello_world，返回它写入文件的内容长度
+fn run_redirected(flags: i32, buf: &mut [u8]) -> usize {
+    let fd = sys_open(FILE.as_ptr(), O_WRONLY | O_CREAT | O_TRUNC).unwrap();
+    let pid = sys_fork().unwrap();
+    if pid == 0 {
+        if sys_dup3(fd, STDOUT, flags).is_err() {
+            sys_exit(1);
+        }
+        let argv = [PROGRAM.as_ptr(), ptr::null()];
+        let _ = sys_exec(PROGRAM.as_ptr(), argv.as_ptr());
+        sys_exit(127);
+    }
+    sys_close(fd as i32).unwrap();
+    let mut status = 0;
+    assert_eq!(sys_wait4(pid as isize, &mut status, 0), Ok(pid));
+    assert_eq!(status, 0);
+    let fd = sys_open(FILE.as_ptr(), O_RDONLY).unwrap();
+    let len = sys_read(fd, buf.as_mut_ptr(), buf.len()).unwrap();
+    sys_close(fd as i32).unwrap();
+    len
+}
+
+#[no_mangle]
+pub fn main() -> usize {
+    let fd = sys_open(FILE.as_ptr(), O_WRONLY | O_CREAT | O_TRUNC).unwrap();
+    assert_eq!(sys_dup3(fd, fd, 0), Err(Errno::EINVAL));
+    assert_eq!(sys_dup3(fd, 10, O_APPEND), Err(Errno::EINVAL));
+    assert_eq!(sys_dup3(fd, 10, O_CLOEXEC), Ok(10));
+    assert_eq!(sys_fcntl(10, F_GETFD, 0), Ok(FD_CLOEXEC));
+    assert_eq!(sys_fcntl(fd, F_GETFD, 0), Ok(0));
+    sys_close(10).unwrap();
+    sys_close(fd as i32).unwrap();
+    println!("dup3 sets FD_CLOEXEC");
+
+    // 新程序继承复制得到的标准输出
+    let mut buf = [0u8; 64];
+    let len = run_redirected(0, &mut buf);
+    let output = str::from_utf8(&buf[..len]).unwrap();
+    println!("exec output = {}", output.lines().next().unwrap_or(""));
+    assert!(buf[..len].starts_with(b"Hello world!"));
+
+    // 带有 O_CLOEXEC 的标准输出在 exec 时被关闭，新程序的输出全部失败
+    assert_eq!(run_redirected(O_CLOEXEC, &mut buf), 0);
+    println!("exec closes FD_CLOEXEC descriptors");
+    println!("dup3_test pass.");
+    0
+}
@@ -4,17 +4,37 @@
 #[macro_use]
 extern crate user;
 
-use user::syscall::sys_fork;
+use user::syscall::{sys_exit, sys_fork, sys_wait4};
 
 #[no_mangle]
 pub fn main() -> usize {
-    let tid = sys_fork();
-    let tid = sys_fork();
-    if tid == 0 {
+    // 子进程得到地址空间的副本，它的修改对父进程不可见
+    let mut value = 1;
+    let pid = sys_fork().unwrap();
+    if pid == 0 {
+        value = 2;
+        println!("I am child, value = {}", value);
+        sys_exit(0x42);
+    }
+    let mut status = 0;
+    assert_eq!(sys_wait4(pid as isize, &mut status, 0), Ok(pid));
+    assert_eq!(status, 0x42 << 8);
+    assert_eq!(value, 1);
+    println!("I am father, value = {}", value);
+
+    // 两次 fork 后共有四个进程
+    let first = sys_fork().unwrap();
+    let second = sys_fork().unwrap();
+    if second == 0 {
         println!("I am child");
     } else {
         println!("I am father");
     }
-    println!("ret tid is: {}", tid);
+    println!("ret tid is: {}, {}", first, second);
+    // 等所有子进程结束，init 退出时内核会关机
+    while sys_wait4(-1, &mut status, 0).is_ok() {}
+    if first != 0 && second != 0 {
+        println!("fork_test pass.");
+    }
     0
-}
\ No newline at end of file
+}
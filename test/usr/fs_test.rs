@@ -0,0 +1,125 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user;
+
+use core::str;
+use user::errno::Errno;
+use user::io::*;
+use user::syscall::{
+    sys_close, sys_fstat, sys_getdents64, sys_linkat, sys_mkdirat, sys_open, sys_pread, sys_pwrite,
+    sys_read, sys_renameat, sys_unlinkat, sys_write, Stat,
+};
+
+// 根文件系统中已有的文件与目录；以 overlay 为根时它们在只读的下层
+const DIR: &str = "/rust\0";
+const LOWER: &str = "/rust/hello_world\0";
+const FILE: &str = "/rust/fs_temp\0";
+const RENAMED: &str = "/rust/fs_temp2\0";
+const LINK: &str = "/rust/fs_link\0";
+const SUBDIR: &str = "/rust/fs_temp_dir\0";
+const NESTED: &str = "/rust/fs_temp_dir/file\0";
+const TEXT: &str = "written to the upper layer\n";
+
+fn stat(path: &str) -> Stat {
+    let fd = sys_open(path.as_ptr(), O_RDONLY).unwrap();
+    let mut stat = Stat::default();
+    sys_fstat(fd, &mut stat).unwrap();
+    sys_close(fd as i32).unwrap();
+    stat
+}
+
+// 目录中是否有名为 name 的项
+fn dir_contains(path: &str, name: &str) -> bool {
+    let fd = sys_open(path.as_ptr(), O_RDONLY).unwrap();
+    let mut buf = [0u8; 256];
+    let mut found = false;
+    loop {
+        let len = sys_getdents64(fd, &mut buf).unwrap();
+        if len == 0 {
+            break;
+        }
+        // struct linux_dirent64 { d_ino: u64, d_off: i64, d_reclen: u16, d_type: u8, d_name: [u8] }
+        let mut pos = 0;
+        while pos < len {
+            let reclen = u16::from_le_bytes([buf[pos + 16], buf[pos + 17]]) as usize;
+            let entry = &buf[pos + 19..pos + reclen];
+            let entry_len = entry.iter().position(|&b| b == 0).unwrap_or(entry.len());
+            found |= &entry[..entry_len] == name.as_bytes();
+            pos += reclen;
+        }
+    }
+    sys_close(fd as i32).unwrap();
+    found
+}
+
+#[no_mangle]
+pub fn main() -> usize {
+    // 能找到并读取根文件系统中已有的文件
+    let size = stat(LOWER).size;
+    assert!(size > 0);
+    let fd = sys_open(LOWER.as_ptr(), O_RDWR).unwrap();
+    let mut magic = [0u8; 4];
+    assert_eq!(sys_pread(fd, magic.as_mut_ptr(), 4, 0), Ok(4));
+    assert_eq!(&magic, b"\x7fELF");
+    println!("lookup ok.");
+
+    // 写入已有的文件时复制到上层，写回相同的内容，文件保持不变
+    assert_eq!(sys_pwrite(fd, magic.as_ptr(), 4, 0), Ok(4));
+    let mut again = [0u8; 4];
+    assert_eq!(sys_pread(fd, again.as_mut_ptr(), 4, 0), Ok(4));
+    assert_eq!(again, magic);
+    let mut st = Stat::default();
+    sys_fstat(fd, &mut st).unwrap();
+    assert_eq!(st.size, size);
+    sys_close(fd as i32).unwrap();
+    assert_eq!(stat(LOWER).size, size);
+    println!("copy-up ok.");
+
+    // 在已有的目录中创建文件，目录项与已有的文件一起列出
+    let fd = sys_open(FILE.as_ptr(), O_RDWR | O_CREAT | O_EXCL).unwrap();
+    assert_eq!(sys_write(fd, TEXT.as_ptr(), TEXT.len()), Ok(TEXT.len()));
+    sys_close(fd as i32).unwrap();
+    assert_eq!(
+        sys_open(FILE.as_ptr(), O_RDWR | O_CREAT | O_EXCL),
+        Err(Errno::EEXIST)
+    );
+    assert!(dir_contains(DIR, "hello_world") && dir_contains(DIR, "fs_temp"));
+    println!("create ok.");
+
+    // 改名后旧的名字不再存在
+    sys_renameat(AT_FDCWD, FILE.as_ptr(), AT_FDCWD, RENAMED.as_ptr()).unwrap();
+    assert_eq!(sys_open(FILE.as_ptr(), O_RDONLY), Err(Errno::ENOENT));
+    let fd = sys_open(RENAMED.as_ptr(), O_RDONLY).unwrap();
+    let mut buf = [0u8; 64];
+    let len = sys_read(fd, buf.as_mut_ptr(), buf.len()).unwrap();
+    sys_close(fd as i32).unwrap();
+    assert_eq!(str::from_utf8(&buf[..len]), Ok(TEXT));
+    sys_unlinkat(AT_FDCWD, RENAMED.as_ptr(), 0).unwrap();
+    assert!(!dir_contains(DIR, "fs_temp2"));
+    println!("rename and unlink ok.");
+
+    // 已有文件的硬链接被删除后，原来的文件不受影响
+    sys_linkat(AT_FDCWD, LOWER.as_ptr(), AT_FDCWD, LINK.as_ptr(), 0).unwrap();
+    assert_eq!(stat(LINK).size, size);
+    sys_unlinkat(AT_FDCWD, LINK.as_ptr(), 0).unwrap();
+    assert_eq!(sys_open(LINK.as_ptr(), O_RDONLY), Err(Errno::ENOENT));
+    assert_eq!(stat(LOWER).size, size);
+    println!("link ok.");
+
+    // 非空目录不能删除
+    sys_mkdirat(AT_FDCWD, SUBDIR.as_ptr(), 0o755).unwrap();
+    let fd = sys_open(FILE.as_ptr(), O_WRONLY | O_CREAT).unwrap();
+    sys_close(fd as i32).unwrap();
+    sys_renameat(AT_FDCWD, FILE.as_ptr(), AT_FDCWD, NESTED.as_ptr()).unwrap();
+    assert_eq!(
+        sys_unlinkat(AT_FDCWD, SUBDIR.as_ptr(), AT_REMOVEDIR),
+        Err(Errno::ENOTEMPTY)
+    );
+    sys_unlinkat(AT_FDCWD, NESTED.as_ptr(), 0).unwrap();
+    sys_unlinkat(AT_FDCWD, SUBDIR.as_ptr(), AT_REMOVEDIR).unwrap();
+    assert_eq!(sys_open(SUBDIR.as_ptr(), O_RDONLY), Err(Errno::ENOENT));
+    println!("fs_test pass.");
+    0
+}
@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user;
+
+use core::slice;
+use user::errno::{Errno, SysResult};
+use user::io::*;
+use user::syscall::{sys_brk, sys_exit, sys_fork, sys_mmap, sys_mprotect, sys_munmap, sys_wait4};
+
+const PAGE_SIZE: usize = 4096;
+const USER_END: usize = 0x40_0000_0000;
+
+fn mmap_anonymous(addr: usize, len: usize, flags: usize) -> SysResult {
+    sys_mmap(
+        addr,
+        len,
+        PROT_READ | PROT_WRITE,
+        MAP_PRIVATE | MAP_ANONYMOUS | flags,
+        usize::max_value(),
+        0,
+    )
+}
+
+#[no_mangle]
+pub fn main() -> usize {
+    // 堆可以增长和收缩，新增的部分可读写
+    let start = sys_brk(0).unwrap();
+    assert_eq!(sys_brk(start + 2 * PAGE_SIZE), Ok(start + 2 * PAGE_SIZE));
+    let heap = unsafe { slice::from_raw_parts_mut(start as *mut u8, 2 * PAGE_SIZE) };
+    heap.iter_mut().for_each(|b| *b = 0x5a);
+    assert_eq!(sys_brk(start), Ok(start));
+    // 超出用户地址空间时不变
+    assert_eq!(sys_brk(USER_END + PAGE_SIZE), Ok(start));
+    println!("brk ok.");
+
+    // 匿名映射的内容为 0，fork 出的子进程修改的是自己的副本
+    let len = 3 * PAGE_SIZE;
+    let addr = mmap_anonymous(0, len, 0).unwrap();
+    let area = unsafe { slice::from_raw_parts_mut(addr as *mut u8, len) };
+    assert!(area.iter().all(|&b| b == 0));
+    area[0] = 1;
+    let pid = sys_fork().unwrap();
+    if pid == 0 {
+        area[0] = 2;
+        sys_exit(area[0] as usize);
+    }
+    let mut status = 0;
+    assert_eq!(sys_wait4(pid as isize, &mut status, 0), Ok(pid));
+    assert_eq!(status, 2 << 8);
+    assert_eq!(area[0], 1);
+    println!("mmap ok.");
+
+    assert_eq!(sys_mprotect(addr + PAGE_SIZE, PAGE_SIZE, PROT_READ), Ok(0));
+    assert_eq!(sys_munmap(addr, len), Ok(0));
+    // 范围内有未映射的页
+    assert_eq!(sys_mprotect(addr, PAGE_SIZE, PROT_READ), Err(Errno::ENOMEM));
+    println!("mprotect and munmap ok.");
+
+    // 不能映射或解除用户地址空间以外的区域
+    assert_eq!(
+        mmap_anonymous(USER_END, PAGE_SIZE, MAP_FIXED),
+        Err(Errno::EINVAL)
+    );
+    assert_eq!(sys_munmap(USER_END, PAGE_SIZE), Err(Errno::EINVAL));
+    assert_eq!(
+        mmap_anonymous(0, USER_END + PAGE_SIZE, 0),
+        Err(Errno::EINVAL)
+    );
+    println!("mm_test pass.");
+    0
+}
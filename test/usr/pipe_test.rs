@@ -6,47 +6,48 @@ extern crate alloc;
 #[macro_use]
 extern crate user;
 
-use user::io::*;
-use user::syscall::{
-    sys_pipe,
-    sys_close,
-    sys_read,
-    sys_write,
-    sys_fork,
-};
 use alloc::string::String;
+use user::errno::Errno;
+use user::syscall::{sys_close, sys_exit, sys_fork, sys_pipe, sys_read, sys_wait4, sys_write};
+
+const MESSAGE: &str = "Hello world!";
 
 #[no_mangle]
 pub fn main() -> usize {
     let mut pipefd: [i32; 2] = [0; 2];
-    sys_pipe(&mut pipefd);
+    sys_pipe(&mut pipefd).unwrap();
     println!("fd_read = {}, fd_write = {}", pipefd[0], pipefd[1]);
-    let pid = sys_fork();
+    let pid = sys_fork().unwrap();
     if pid == 0 {
-        // child process, read from pipe
-        // close write end of pipe
-        sys_close(pipefd[1]);
-        let mut string = String::from("");
-        let ch: u8 = 0;
-        loop {
-            sys_read(pipefd[0] as usize, &ch as *const u8, 1);
-            if ch == 0 {
-                break;
-            }
+        // 子进程关闭写端后读到父进程关闭写端为止
+        sys_close(pipefd[1]).unwrap();
+        let mut string = String::new();
+        let mut ch = 0u8;
+        while sys_read(pipefd[0] as usize, &mut ch, 1).unwrap() == 1 {
             string.push(ch as char);
         }
         println!("message received in child process = {}", string);
-    } else {
-        // parent process, write to pipe
-        // close read end of pipe
-        sys_close(pipefd[0]);
-        let string = String::from("Hello world!");
-        for ch in string.bytes() {
-            sys_write(pipefd[1] as usize, &ch as *const u8, 1);
-        }
-        let ch: u8 = 0;
-        sys_write(pipefd[1] as usize, &ch as *const u8, 1);
-        println!("message sent to child process pid {}!", pid);
+        sys_exit(if string == MESSAGE { 0 } else { 1 });
     }
+    // 父进程关闭读端，逐个字节写入后关闭写端，子进程随后读到文件结尾
+    sys_close(pipefd[0]).unwrap();
+    for ch in MESSAGE.bytes() {
+        assert_eq!(sys_write(pipefd[1] as usize, &ch, 1), Ok(1));
+    }
+    sys_close(pipefd[1]).unwrap();
+    println!("message sent to child process pid {}!", pid);
+    let mut status = 0;
+    assert_eq!(sys_wait4(pid as isize, &mut status, 0), Ok(pid));
+    assert_eq!(status, 0);
+
+    // 读端全部关闭后写入失败
+    sys_pipe(&mut pipefd).unwrap();
+    sys_close(pipefd[0]).unwrap();
+    assert_eq!(
+        sys_write(pipefd[1] as usize, MESSAGE.as_ptr(), MESSAGE.len()),
+        Err(Errno::EPIPE)
+    );
+    sys_close(pipefd[1]).unwrap();
+    println!("pipe_test pass.");
     0
 }
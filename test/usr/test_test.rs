@@ -21,15 +21,15 @@ const TEXT: &'static str = "Hello world!\0";
 #[no_mangle]
 pub fn main() -> usize {
     // 将字符串写到文件 temp 中
-    let write_fd = sys_open(FILE.as_ptr(), O_WRONLY);
-    sys_write(write_fd as usize, TEXT.as_ptr(), TEXT.len());
+    let write_fd = sys_open(FILE.as_ptr(), O_WRONLY | O_CREAT | O_TRUNC).unwrap();
+    sys_write(write_fd, TEXT.as_ptr(), TEXT.len()).unwrap();
     println!("write to file 'temp' successfully...");
-    sys_close(write_fd as i32);
+    sys_close(write_fd as i32).unwrap();
 
     // 将字符串从文件 temp 读入内存
-    let read_fd = sys_open(FILE.as_ptr(), O_RDONLY);
+    let read_fd = sys_open(FILE.as_ptr(), O_RDONLY).unwrap();
     let mut read = [0u8; BUFFER_SIZE];
-    sys_read(read_fd as usize, &read[0] as *const u8, BUFFER_SIZE);
+    sys_read(read_fd, &read[0] as *const u8, BUFFER_SIZE).unwrap();
     println!("read from file 'temp' successfully...");
 
     // 检查功能是否正确
@@ -40,6 +40,6 @@ pub fn main() -> usize {
         putchar(read[i] as char);
     }
     putchar('\n');
-    sys_close(read_fd as i32);
+    sys_close(read_fd as i32).unwrap();
     0
 }
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user;
+
+use user::errno::Errno;
+use user::io::*;
+use user::syscall::{sys_close, sys_ioctl, sys_open, sys_write, Termios};
+
+const FILE: &str = "tty_temp\0";
+const TEXT: &str = "one write, several bytes\n";
+
+fn get_termios(fd: usize) -> Result<Termios, Errno> {
+    let mut termios = Termios::default();
+    sys_ioctl(fd, TCGETS, &mut termios as *mut Termios as usize)?;
+    Ok(termios)
+}
+
+fn set_termios(fd: usize, termios: &Termios) {
+    sys_ioctl(fd, TCSETS, termios as *const Termios as usize).unwrap();
+}
+
+#[no_mangle]
+pub fn main() -> usize {
+    // 终端默认处于规范模式并回显输入
+    let cooked = get_termios(STDIN).unwrap();
+    assert!(cooked.lflag & ICANON != 0 && cooked.lflag & ECHO != 0);
+
+    let mut raw = cooked;
+    raw.lflag &= !(ICANON | ECHO);
+    set_termios(STDIN, &raw);
+    assert_eq!(get_termios(STDIN).unwrap().lflag, raw.lflag);
+    set_termios(STDIN, &cooked);
+    assert_eq!(get_termios(STDIN).unwrap().lflag, cooked.lflag);
+    println!("TCGETS and TCSETS ok.");
+
+    // 一次写入多个字节
+    assert_eq!(sys_write(STDOUT, TEXT.as_ptr(), TEXT.len()), Ok(TEXT.len()));
+
+    // 普通文件不是终端
+    let fd = sys_open(FILE.as_ptr(), O_WRONLY | O_CREAT | O_TRUNC).unwrap();
+    assert_eq!(get_termios(fd).err(), Some(Errno::ENOTTY));
+    sys_close(fd as i32).unwrap();
+    println!("tty_test pass.");
+    0
+}
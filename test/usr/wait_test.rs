@@ -4,55 +4,49 @@
 #[macro_use]
 extern crate user;
 
-use user::syscall::{sys_exit as exit, sys_yield as yield_now, sys_fork as fork, sys_wait as waitpid};
+use user::errno::Errno;
+use user::io::*;
+use user::syscall::{sys_exit, sys_fork, sys_getpid, sys_kill, sys_wait4};
+
+const SIGSTOP: usize = 19;
 
 #[no_mangle]
-pub fn main() -> isize {
+pub fn main() -> usize {
     let magic: usize = 0x10384;
-    let mut pid: usize = 0;
-    let mut code: i32 = 0;
     println!("I am the parent. Forking the child...");
-    pid = fork() as usize;
-    if (pid == 0) {
+    let pid = sys_fork().unwrap();
+    if pid == 0 {
         println!("I am the child.");
-        yield_now();
-        yield_now();
-        yield_now();
-        yield_now();
-        yield_now();
-        yield_now();
-        yield_now();
-        exit(magic);
-    }
-    else {
-        println!("I am parent, fork a child pid {}", pid);
-    }
-    if pid <= 0 {
-        panic!("pid <= 0");
+        sys_exit(magic);
     }
+    println!("I am parent, fork a child pid {}", pid);
     println!("I am the parent, waiting now..");
-    let wait_pid = waitpid(pid, &mut code);
-    println!("{}, {:x}", wait_pid, code);
-    if wait_pid != 0 || code != magic as i32 {
-        panic!("wait_test1 fail");
-    }
-    if !(waitpid(pid, &mut code) != 0) {
-        panic!("wait_test2 fail");
-    }
+    let mut status = 0;
+    assert_eq!(sys_wait4(pid as isize, &mut status, 0), Ok(pid));
+    // 只保留退出码的低 8 位
+    assert_eq!(status, ((magic & 0xff) << 8) as i32);
+    assert_eq!(sys_wait4(pid as isize, &mut status, 0), Err(Errno::ECHILD));
     println!("waitpid {} ok.", pid);
+
+    // 子进程一直运行，直到被暂停、继续后被杀死
+    let pid = sys_fork().unwrap();
+    if pid == 0 {
+        loop {
+            let _ = sys_getpid();
+        }
+    }
+    assert_eq!(sys_wait4(pid as isize, &mut status, WNOHANG), Ok(0));
+    sys_kill(pid as isize, SIGSTOP).unwrap();
+    assert_eq!(sys_wait4(pid as isize, &mut status, WUNTRACED), Ok(pid));
+    assert_eq!(status, (SIGSTOP << 8 | 0x7f) as i32);
+    // 不带 WUNTRACED 时不报告暂停的子进程
+    assert_eq!(sys_wait4(pid as isize, &mut status, WNOHANG), Ok(0));
+    println!("child {} stopped.", pid);
+    sys_kill(pid as isize, SIGCONT).unwrap();
+    sys_kill(pid as isize, SIGKILL).unwrap();
+    assert_eq!(sys_wait4(-1, &mut status, WUNTRACED), Ok(pid));
+    assert_eq!(status, SIGKILL as i32);
+    println!("child {} killed.", pid);
     println!("wait_test pass.");
-    return 0;
+    0
 }
-
-/*
-out put:
-
-I am the parent. Forking the child...
-I am the child.
-I am parent, fork a child pid 2
-I am the parent, waiting now..
-thread 2 exited, exit code = 66436
-waitpid 2 ok.
-wait_test pass.
-thread 1 exited, exit code = 0
-*/
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate user;
+
+use user::io::*;
+use user::syscall::{sys_close, sys_open, sys_read, sys_write};
+
+#[no_mangle]
+pub fn main() -> usize {
+    print!("file: ");
+    let mut path = getline().unwrap_or_default();
+    path.push('\0');
+    let fd = match sys_open(path.as_ptr(), O_RDONLY) {
+        Ok(fd) => fd,
+        Err(err) => {
+            println!("cat: {}: {}", path.trim_end_matches('\0'), err);
+            return 1;
+        }
+    };
+    let mut buf = [0u8; 128];
+    loop {
+        match sys_read(fd, buf.as_mut_ptr(), buf.len()) {
+            Ok(0) => break,
+            Ok(len) => {
+                let _ = sys_write(STDOUT, buf.as_ptr(), len);
+            }
+            Err(err) => {
+                println!("cat: {}", err);
+                break;
+            }
+        }
+    }
+    let _ = sys_close(fd as i32);
+    0
+}
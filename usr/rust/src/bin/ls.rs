@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate user;
+
+use core::str;
+use user::io::*;
+use user::syscall::{sys_close, sys_getdents64, sys_open};
+
+#[no_mangle]
+pub fn main() -> usize {
+    print!("directory: ");
+    let mut path = getline().unwrap_or_default();
+    if path.is_empty() {
+        path.push('/');
+    }
+    path.push('\0');
+    let fd = match sys_open(path.as_ptr(), O_RDONLY) {
+        Ok(fd) => fd,
+        Err(err) => {
+            println!("ls: {}: {}", path.trim_end_matches('\0'), err);
+            return 1;
+        }
+    };
+    let mut buf = [0u8; 256];
+    loop {
+        let len = match sys_getdents64(fd, &mut buf) {
+            Ok(len) => len,
+            Err(err) => {
+                println!("ls: {}", err);
+                let _ = sys_close(fd as i32);
+                return 1;
+            }
+        };
+        if len == 0 {
+            break;
+        }
+        // struct linux_dirent64 { d_ino: u64, d_off: i64, d_reclen: u16, d_type: u8, d_name: [u8] }
+        let mut pos = 0;
+        while pos < len {
+            let reclen = u16::from_le_bytes([buf[pos + 16], buf[pos + 17]]) as usize;
+            let type_ = buf[pos + 18];
+            let name = &buf[pos + 19..pos + reclen];
+            let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            let name = str::from_utf8(&name[..name_len]).unwrap_or("?");
+            // DT_DIR = 4
+            if type_ == 4 {
+                println!("{}/", name);
+            } else {
+                println!("{}", name);
+            }
+            pos += reclen;
+        }
+    }
+    let _ = sys_close(fd as i32);
+    0
+}
@@ -4,22 +4,11 @@
 #[macro_use]
 extern crate user;
 
-use user::io::getc;
-
-const LF: u8 = 0x0au8;
-const CR: u8 = 0x0du8;
+use user::io::getline;
 
+// 终端负责回显与行编辑，输入 ^D 结束
 #[no_mangle]
 pub fn main() {
     println!("Welcome to notebook!");
-    loop {
-        let c = getc();
-        match c {
-            LF | CR => {
-                print!("{}", LF as char);
-                print!("{}", CR as char)
-            }
-            _ => print!("{}", c as char),
-        }
-    }
+    while getline().is_some() {}
 }
@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate user;
+
+use user::io::*;
+use user::syscall::{sys_fstatat, Stat};
+
+#[no_mangle]
+pub fn main() -> usize {
+    print!("file: ");
+    let mut path = getline().unwrap_or_default();
+    path.push('\0');
+    let mut stat = Stat::default();
+    if let Err(err) = sys_fstatat(AT_FDCWD, path.as_ptr(), &mut stat) {
+        println!("stat: {}: {}", path.trim_end_matches('\0'), err);
+        return 1;
+    }
+    let type_ = match stat.mode & S_IFMT {
+        S_IFREG => "regular file",
+        S_IFDIR => "directory",
+        S_IFCHR => "character device",
+        _ => "other",
+    };
+    println!("  File: {}", path.trim_end_matches('\0'));
+    println!("  Size: {}\tBlocks: {}\t{}", stat.size, stat.blocks, type_);
+    println!(
+        "Device: {}\tInode: {}\tLinks: {}",
+        stat.dev, stat.ino, stat.nlink
+    );
+    println!(
+        "Access: {:o}\tUid: {}\tGid: {}",
+        stat.mode & 0o7777,
+        stat.uid,
+        stat.gid
+    );
+    0
+}
@@ -6,35 +6,238 @@ extern crate alloc;
 #[macro_use]
 extern crate user;
 
-const LF: u8 = 0x0au8;
-const CR: u8 = 0x0du8;
+use alloc::{string::String, vec::Vec};
+use core::ptr;
+use core::str;
+use user::errno::Errno;
+use user::io::*;
+use user::syscall::{
+    sys_chdir, sys_close, sys_dup3, sys_exec, sys_exit, sys_fork, sys_getcwd, sys_getpgid,
+    sys_getpid, sys_kill, sys_open, sys_setpgid, sys_signal, sys_wait4,
+};
 
-use alloc::string::String;
-use user::io::getc;
-use user::syscall::sys_exec;
+// 由 fork 启动、还没有结束的程序；每个作业自成一个进程组，组号就是它的 tid
+struct Job {
+    id: usize,
+    pid: usize,
+    command: String,
+    stopped: bool,
+}
 
 #[no_mangle]
 pub fn main() {
     println!("Rust user shell");
-    let mut line: String = String::new();
-    print!(">> ");
+    // ^C、^Z 与 ^\ 只作用于前台作业
+    for &sig in [SIGINT, SIGQUIT, SIGTSTP].iter() {
+        let _ = sys_signal(sig, SIG_IGN);
+    }
+    let _ = tcsetpgrp(STDIN, sys_getpgid(0).unwrap_or(0));
+    let mut jobs: Vec<Job> = Vec::new();
     loop {
-        let c = getc();
-        match c {
-            LF | CR => {
-                println!("");
-                if !line.is_empty() {
-                    line.push('\0');
-                    println!("searching for program {}", line);
-                    sys_exec(line.as_ptr());
-                    line.clear();
+        update_jobs(&mut jobs);
+        print!(">> ");
+        let mut line = match getline() {
+            Some(line) => line,
+            None => {
+                println!("exit");
+                return;
+            }
+        };
+        if !line.is_empty() {
+            run(&mut line, &mut jobs);
+        }
+    }
+}
+
+// cd、pwd、jobs、fg 与 bg 是内建命令，其余的作为程序路径执行
+fn run(line: &mut String, jobs: &mut Vec<Job>) {
+    if line == "pwd" {
+        let mut buf = [0u8; 256];
+        match sys_getcwd(&mut buf) {
+            Ok(len) => println!("{}", str::from_utf8(&buf[..len - 1]).unwrap()),
+            Err(err) => println!("pwd: {}", err),
+        }
+    } else if line == "cd" || line.starts_with("cd ") {
+        let mut dir = String::from(line[2..].trim());
+        if dir.is_empty() {
+            dir.push('/');
+        }
+        dir.push('\0');
+        if let Err(err) = sys_chdir(dir.as_ptr()) {
+            println!("cd: {}: {}", err, dir.trim_end_matches('\0'));
+        }
+    } else if line == "jobs" {
+        for job in jobs.iter() {
+            let state = if job.stopped { "Stopped" } else { "Running" };
+            println!("[{}] {} {}\t{}", job.id, job.pid, state, job.command);
+        }
+    } else if line == "fg" || line.starts_with("fg ") {
+        match find_job(jobs, &line[2..]) {
+            Some(i) => {
+                println!("{}", jobs[i].command);
+                wait_foreground(jobs, i, true);
+            }
+            None => println!("fg: no such job"),
+        }
+    } else if line == "bg" || line.starts_with("bg ") {
+        match find_job(jobs, &line[2..]) {
+            Some(i) => {
+                let job = &mut jobs[i];
+                job.stopped = false;
+                let _ = sys_kill(-(job.pid as isize), SIGCONT);
+                println!("[{}] {} &", job.id, job.command);
+            }
+            None => println!("bg: no such job"),
+        }
+    } else {
+        exec_with_redirect(line, jobs);
+    }
+}
+
+// 参数为空时是最近的作业，否则是作业号，可以带 %
+fn find_job(jobs: &[Job], arg: &str) -> Option<usize> {
+    let arg = arg.trim().trim_start_matches('%');
+    if arg.is_empty() {
+        return jobs.len().checked_sub(1);
+    }
+    let id: usize = arg.parse().ok()?;
+    jobs.iter().position(|job| job.id == id)
+}
+
+// 把终端交给作业，等待它结束或暂停后收回终端
+// resume 为真时先让暂停的作业继续运行
+fn wait_foreground(jobs: &mut Vec<Job>, i: usize, resume: bool) {
+    let pid = jobs[i].pid;
+    let _ = tcsetpgrp(STDIN, pid);
+    if resume {
+        jobs[i].stopped = false;
+        let _ = sys_kill(-(pid as isize), SIGCONT);
+    }
+    let mut status = 0;
+    let result = sys_wait4(pid as isize, &mut status, WUNTRACED);
+    let _ = tcsetpgrp(STDIN, sys_getpgid(0).unwrap_or(0));
+    match result {
+        Ok(_) if status & 0xff == 0x7f => {
+            jobs[i].stopped = true;
+            println!("\n[{}] Stopped\t{}", jobs[i].id, jobs[i].command);
+        }
+        Ok(_) => {
+            // 被 ^C 等信号终止时，终端只回显了 ^C，换行后再显示提示符
+            if status & 0x7f != 0 {
+                println!();
+            }
+            jobs.remove(i);
+        }
+        Err(_) => {
+            jobs.remove(i);
+        }
+    }
+}
+
+// 显示提示符前报告已经结束或被暂停的后台作业
+fn update_jobs(jobs: &mut Vec<Job>) {
+    let mut i = 0;
+    while i < jobs.len() {
+        let mut status = 0;
+        match sys_wait4(jobs[i].pid as isize, &mut status, WNOHANG | WUNTRACED) {
+            Ok(0) => {}
+            Ok(_) if status & 0xff == 0x7f => {
+                if !jobs[i].stopped {
+                    jobs[i].stopped = true;
+                    println!("[{}] Stopped\t{}", jobs[i].id, jobs[i].command);
                 }
-                print!(">> ");
             }
             _ => {
-                print!("{}", c as char);
-                line.push(c as char);
+                println!("[{}] Done\t{}", jobs[i].id, jobs[i].command);
+                jobs.remove(i);
+                continue;
+            }
+        }
+        i += 1;
+    }
+}
+
+// 支持 < file、> file 与 >> file，在子进程中替换标准输入输出后 exec
+// 以 & 结尾时在后台运行，其余的词依次作为程序路径与参数
+fn exec_with_redirect(line: &str, jobs: &mut Vec<Job>) {
+    let background = line.trim_end().ends_with('&');
+    let line = line.trim().trim_end_matches('&').trim_end();
+    let mut args: Vec<String> = Vec::new();
+    let mut redirects: Vec<(usize, String, i32)> = Vec::new();
+    let mut words = line.split_whitespace();
+    while let Some(word) = words.next() {
+        let (fd, flags) = match word {
+            "<" => (STDIN, O_RDONLY),
+            ">" => (STDOUT, O_WRONLY | O_CREAT | O_TRUNC),
+            ">>" => (STDOUT, O_WRONLY | O_CREAT | O_APPEND),
+            word => {
+                args.push(String::from(word) + "\0");
+                continue;
+            }
+        };
+        match words.next() {
+            Some(path) => redirects.push((fd, String::from(path) + "\0", flags)),
+            None => {
+                println!("syntax error: missing file after {}", word);
+                return;
             }
         }
     }
+    if args.is_empty() {
+        println!("syntax error: missing program");
+        return;
+    }
+    println!("searching for program {}", args[0].trim_end_matches('\0'));
+    match sys_fork() {
+        Ok(0) => exec_child(&args, &redirects, !background),
+        Ok(pid) => {
+            // 子进程自己也会设置，无论谁先运行，exec 前它都已在自己的进程组中
+            let _ = sys_setpgid(pid, pid);
+            let id = jobs.last().map_or(1, |job| job.id + 1);
+            jobs.push(Job {
+                id,
+                pid,
+                command: String::from(line),
+                stopped: false,
+            });
+            if background {
+                println!("[{}] {}", id, pid);
+            } else {
+                wait_foreground(jobs, jobs.len() - 1, false);
+            }
+        }
+        Err(err) => println!("fork: {}", err),
+    }
+}
+
+// 在子进程中运行，不返回；先进入自己的进程组，前台作业还要取得终端，
+// 这样新程序开始运行时 ^C 与 ^Z 已经只作用于它；shell 忽略的信号在新程序中恢复默认动作
+fn exec_child(args: &[String], redirects: &[(usize, String, i32)], foreground: bool) -> ! {
+    let program = args[0].trim_end_matches('\0');
+    let _ = sys_setpgid(0, 0);
+    if foreground {
+        let _ = tcsetpgrp(STDIN, sys_getpid().unwrap_or(0));
+    }
+    for &sig in [SIGINT, SIGQUIT, SIGTSTP].iter() {
+        let _ = sys_signal(sig, SIG_DFL);
+    }
+    for (fd, path, flags) in redirects.iter() {
+        let file = match sys_open(path.as_ptr(), *flags) {
+            Ok(file) => file,
+            Err(err) => {
+                println!("cannot open {}: {}", path.trim_end_matches('\0'), err);
+                sys_exit(1);
+            }
+        };
+        let _ = sys_dup3(file, *fd, 0);
+        let _ = sys_close(file as i32);
+    }
+    let mut argv: Vec<*const u8> = args.iter().map(|arg| arg.as_ptr()).collect();
+    argv.push(ptr::null());
+    match sys_exec(args[0].as_ptr(), argv.as_ptr()) {
+        Err(Errno::ENOENT) => println!("{}: command not found", program),
+        Err(err) => println!("{}: {}", program, err),
+        Ok(_) => unreachable!(),
+    }
+    sys_exit(127);
 }
@@ -1,15 +1,16 @@
+use crate::errno::SysResult;
+use crate::syscall::sys_ioctl;
 use crate::syscall::sys_read;
 use crate::syscall::sys_write;
+use alloc::string::String;
 use core::fmt::{self, Write};
 
 pub fn putchar(ch: char) {
-    sys_write(STDOUT, &ch as *const char as *const u8, 1);
+    let _ = sys_write(STDOUT, &ch as *const char as *const u8, 1);
 }
 
 pub fn puts(s: &str) {
-    for ch in s.chars() {
-        putchar(ch);
-    }
+    let _ = sys_write(STDOUT, s.as_ptr(), s.len());
 }
 
 #[macro_export]
@@ -43,13 +44,82 @@ pub const STDOUT: usize = 1;
 
 pub fn getc() -> u8 {
     let mut c = 0u8;
-    assert_eq!(sys_read(STDIN, &mut c, 1), 1);
+    assert_eq!(sys_read(STDIN, &mut c, 1), Ok(1));
     c
 }
 
+// 读入一行，不包含结尾的换行，回显与行编辑由终端完成
+// 在行首读到文件结尾时返回 None
+pub fn getline() -> Option<String> {
+    let mut line = String::new();
+    let mut c = 0u8;
+    loop {
+        match sys_read(STDIN, &mut c, 1) {
+            Ok(1) if c == b'\n' => return Some(line),
+            Ok(1) => line.push(c as char),
+            _ if line.is_empty() => return None,
+            _ => return Some(line),
+        }
+    }
+}
+
+// 让进程组 pgrp 成为终端 fd 的前台进程组
+pub fn tcsetpgrp(fd: usize, pgrp: usize) -> SysResult {
+    let pgrp = pgrp as i32;
+    sys_ioctl(fd, TIOCSPGRP, &pgrp as *const i32 as usize)
+}
+
 pub const O_RDONLY: i32 = 0;    // 只读
 pub const O_WRONLY: i32 = 1;    // 只写
 pub const O_RDWR: i32 = 2;        // 可读可写
 pub const O_CREAT: i32 = 64;    // 打开文件时若文件不存在，创建它
+pub const O_EXCL: i32 = 128;    // 与 O_CREAT 一起使用，文件已存在时失败
+pub const O_TRUNC: i32 = 512;    // 打开文件时将其长度截断为 0
 pub const O_APPEND: i32 = 1024;    // 从文件结尾开始写入
+pub const O_CLOEXEC: i32 = 0o2000000;    // exec 出的程序不继承这个文件描述符
+
+pub const SEEK_SET: usize = 0;
+pub const SEEK_CUR: usize = 1;
+pub const SEEK_END: usize = 2;
+
+pub const S_IFMT: u32 = 0o170000;    // 文件类型的掩码
+pub const S_IFCHR: u32 = 0o020000;
+pub const S_IFDIR: u32 = 0o040000;
+pub const S_IFREG: u32 = 0o100000;
+
+pub const F_DUPFD: usize = 0;
+pub const F_GETFD: usize = 1;
+pub const F_SETFD: usize = 2;
+pub const F_GETFL: usize = 3;
+pub const F_SETFL: usize = 4;
+pub const F_DUPFD_CLOEXEC: usize = 1030;
+pub const FD_CLOEXEC: usize = 1;
+
+pub const AT_FDCWD: isize = -100;    // 相对路径从当前目录开始解析
+pub const AT_REMOVEDIR: usize = 0x200;    // unlinkat 删除目录
+
+pub const TCGETS: usize = 0x5401;
+pub const TCSETS: usize = 0x5402;
+pub const ICANON: u32 = 0o2;    // 规范模式，按行读取并支持行编辑
+pub const ECHO: u32 = 0o10;    // 回显输入的字符
+pub const TIOCGPGRP: usize = 0x540f;    // 读取终端的前台进程组
+pub const TIOCSPGRP: usize = 0x5410;    // 设置终端的前台进程组
+
+pub const SIGINT: usize = 2;    // ^C
+pub const SIGQUIT: usize = 3;    // ^\
+pub const SIGKILL: usize = 9;
+pub const SIGCHLD: usize = 17;    // 子进程结束或暂停
+pub const SIGCONT: usize = 18;    // 让暂停的进程继续运行
+pub const SIGTSTP: usize = 20;    // ^Z
+pub const SIGTTIN: usize = 21;    // 后台进程读取终端
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
+pub const PROT_READ: usize = 0x1;
+pub const PROT_WRITE: usize = 0x2;
+pub const MAP_PRIVATE: usize = 0x02;
+pub const MAP_FIXED: usize = 0x10;    // 必须映射到指定的地址
+pub const MAP_ANONYMOUS: usize = 0x20;    // 不对应文件，内容为 0
 
+pub const WNOHANG: usize = 1;    // 没有子进程结束时立即返回
+pub const WUNTRACED: usize = 2;    // 子进程被暂停时也返回
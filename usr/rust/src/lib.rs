@@ -9,6 +9,7 @@ extern crate alloc;
 #[macro_use]
 pub mod io;
 
+pub mod errno;
 pub mod lang_items;
 pub mod syscall;
 
@@ -1,50 +1,372 @@
+use crate::errno::{Errno, SysResult};
+use crate::io::{AT_FDCWD, SIGCHLD};
+
+// 与内核中的 struct stat 布局相同
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Stat {
+    pub dev: u64,
+    pub ino: u64,
+    pub mode: u32,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub rdev: u64,
+    __pad: u64,
+    pub size: i64,
+    pub blksize: i32,
+    __pad2: i32,
+    pub blocks: i64,
+    pub atime: i64,
+    pub atime_nsec: i64,
+    pub mtime: i64,
+    pub mtime_nsec: i64,
+    pub ctime: i64,
+    pub ctime_nsec: i64,
+    __unused: [u32; 2],
+}
+
+// 与内核中的 struct termios 布局相同
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Termios {
+    pub iflag: u32,
+    pub oflag: u32,
+    pub cflag: u32,
+    pub lflag: u32,
+    pub line: u8,
+    pub cc: [u8; 19],
+}
+
+// riscv64 上的 struct sigaction
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SigAction {
+    pub handler: usize,
+    pub flags: usize,
+    pub mask: u64,
+}
+
 enum SyscallId {
-    Open = 56,
+    Getcwd = 17,
+    Dup = 23,
+    Dup3 = 24,
+    Fcntl = 25,
+    Ioctl = 29,
+    Mkdirat = 34,
+    Unlinkat = 35,
+    Linkat = 37,
+    Renameat = 38,
+    Umount2 = 39,
+    Mount = 40,
+    Chdir = 49,
+    Fchdir = 50,
+    Openat = 56,
     Close = 57,
+    Pipe2 = 59,
+    Getdents64 = 61,
+    Lseek = 62,
     Read = 63,
     Write = 64,
+    Pread64 = 67,
+    Pwrite64 = 68,
+    Newfstatat = 79,
+    Fstat = 80,
     Exit = 93,
+    Kill = 129,
+    RtSigaction = 134,
+    Setpgid = 154,
+    Getpgid = 155,
+    Setsid = 157,
+    Getpid = 172,
+    Brk = 214,
+    Munmap = 215,
+    Clone = 220,
     Exec = 221,
+    Mmap = 222,
+    Mprotect = 226,
+    Wait4 = 260,
+}
+
+#[inline(always)]
+fn sys_call(
+    syscall_id: SyscallId,
+    arg0: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+) -> SysResult {
+    sys_call6(syscall_id, [arg0, arg1, arg2, arg3, 0, 0])
 }
 
+// 参数通过 a0 ~ a5 传递
 #[inline(always)]
-fn sys_call(syscall_id: SyscallId, arg0: usize, arg1: usize, arg2: usize, arg3: usize) -> i64 {
+fn sys_call6(syscall_id: SyscallId, args: [usize; 6]) -> SysResult {
     let id = syscall_id as usize;
     let mut ret: i64;
     unsafe {
         asm!(
             "ecall"
             : "={x10}"(ret)
-            : "{x17}"(id), "{x10}"(arg0), "{x11}"(arg1), "{x12}"(arg2), "{x13}"(arg3)
+            : "{x17}"(id), "{x10}"(args[0]), "{x11}"(args[1]), "{x12}"(args[2]), "{x13}"(args[3]),
+              "{x14}"(args[4]), "{x15}"(args[5])
             : "memory"
             : "volatile"
         );
     }
-    ret
+    // 内核返回 -errno 表示失败
+    if ret < 0 {
+        Err(Errno::from_code(-ret))
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+pub fn sys_mount(source: *const u8, target: *const u8, fstype: *const u8) -> SysResult {
+    sys_call(
+        SyscallId::Mount,
+        source as usize,
+        target as usize,
+        fstype as usize,
+        0,
+    )
+}
+
+pub fn sys_umount2(target: *const u8) -> SysResult {
+    sys_call(SyscallId::Umount2, target as usize, 0, 0, 0)
+}
+
+pub fn sys_openat(dirfd: isize, path: *const u8, flags: i32, mode: u32) -> SysResult {
+    sys_call(
+        SyscallId::Openat,
+        dirfd as usize,
+        path as usize,
+        flags as usize,
+        mode as usize,
+    )
+}
+
+pub fn sys_open(path: *const u8, flags: i32) -> SysResult {
+    sys_openat(AT_FDCWD, path, flags, 0o644)
 }
 
+pub fn sys_mkdirat(dirfd: isize, path: *const u8, mode: u32) -> SysResult {
+    sys_call(
+        SyscallId::Mkdirat,
+        dirfd as usize,
+        path as usize,
+        mode as usize,
+        0,
+    )
+}
+
+pub fn sys_unlinkat(dirfd: isize, path: *const u8, flags: usize) -> SysResult {
+    sys_call(SyscallId::Unlinkat, dirfd as usize, path as usize, flags, 0)
+}
+
+pub fn sys_linkat(
+    olddirfd: isize,
+    oldpath: *const u8,
+    newdirfd: isize,
+    newpath: *const u8,
+    flags: usize,
+) -> SysResult {
+    sys_call6(
+        SyscallId::Linkat,
+        [
+            olddirfd as usize,
+            oldpath as usize,
+            newdirfd as usize,
+            newpath as usize,
+            flags,
+            0,
+        ],
+    )
+}
 
-pub fn sys_open(path: *const u8, flags: i32) -> i64 {
-    sys_call(SyscallId::Open, path as usize, flags as usize, 0, 0)
+pub fn sys_renameat(
+    olddirfd: isize,
+    oldpath: *const u8,
+    newdirfd: isize,
+    newpath: *const u8,
+) -> SysResult {
+    sys_call(
+        SyscallId::Renameat,
+        olddirfd as usize,
+        oldpath as usize,
+        newdirfd as usize,
+        newpath as usize,
+    )
 }
 
-pub fn sys_close(fd: i32) -> i64 {
+pub fn sys_close(fd: i32) -> SysResult {
     sys_call(SyscallId::Close, fd as usize, 0, 0, 0)
 }
 
-pub fn sys_write(fd: usize, base: *const u8, len: usize) -> i64 {
+pub fn sys_write(fd: usize, base: *const u8, len: usize) -> SysResult {
     sys_call(SyscallId::Write, fd, base as usize, len, 0)
 }
 
 pub fn sys_exit(code: usize) -> ! {
-    sys_call(SyscallId::Exit, code, 0, 0, 0);
+    let _ = sys_call(SyscallId::Exit, code, 0, 0, 0);
     loop {}
 }
 
-pub fn sys_read(fd: usize, base: *const u8, len: usize) -> i64 {
+pub fn sys_read(fd: usize, base: *const u8, len: usize) -> SysResult {
     sys_call(SyscallId::Read, fd, base as usize, len, 0)
 }
 
-pub fn sys_exec(path: *const u8) {
-    sys_call(SyscallId::Exec, path as usize, 0, 0, 0);
+// 子进程中返回 0，父进程中返回子进程的 tid
+pub fn sys_fork() -> SysResult {
+    sys_call(SyscallId::Clone, SIGCHLD, 0, 0, 0)
+}
+
+// argv 是以空指针结尾的参数数组，为空指针时内核以 path 作为唯一的参数
+// 成功时不返回，当前进程改为运行新程序
+pub fn sys_exec(path: *const u8, argv: *const *const u8) -> SysResult {
+    sys_call(SyscallId::Exec, path as usize, argv as usize, 0, 0)
+}
+
+// 返回结束或暂停的子线程的 tid，status 的编码与 Linux 相同
+pub fn sys_wait4(pid: isize, status: &mut i32, options: usize) -> SysResult {
+    sys_call(
+        SyscallId::Wait4,
+        pid as usize,
+        status as *mut i32 as usize,
+        options,
+        0,
+    )
+}
+
+pub fn sys_kill(pid: isize, sig: usize) -> SysResult {
+    sys_call(SyscallId::Kill, pid as usize, sig, 0, 0)
+}
+
+// 只支持 SIG_DFL 与 SIG_IGN
+pub fn sys_signal(sig: usize, handler: usize) -> SysResult {
+    let act = SigAction {
+        handler,
+        ..SigAction::default()
+    };
+    sys_call(
+        SyscallId::RtSigaction,
+        sig,
+        &act as *const SigAction as usize,
+        0,
+        8,
+    )
+}
+
+pub fn sys_getpid() -> SysResult {
+    sys_call(SyscallId::Getpid, 0, 0, 0, 0)
+}
+
+pub fn sys_setpgid(pid: usize, pgid: usize) -> SysResult {
+    sys_call(SyscallId::Setpgid, pid, pgid, 0, 0)
+}
+
+pub fn sys_getpgid(pid: usize) -> SysResult {
+    sys_call(SyscallId::Getpgid, pid, 0, 0, 0)
+}
+
+pub fn sys_setsid() -> SysResult {
+    sys_call(SyscallId::Setsid, 0, 0, 0, 0)
+}
+
+// 返回新的 program break，失败时不变；addr 为 0 时只查询
+pub fn sys_brk(addr: usize) -> SysResult {
+    sys_call(SyscallId::Brk, addr, 0, 0, 0)
+}
+
+pub fn sys_mmap(
+    addr: usize,
+    len: usize,
+    prot: usize,
+    flags: usize,
+    fd: usize,
+    offset: usize,
+) -> SysResult {
+    sys_call6(SyscallId::Mmap, [addr, len, prot, flags, fd, offset])
+}
+
+pub fn sys_munmap(addr: usize, len: usize) -> SysResult {
+    sys_call(SyscallId::Munmap, addr, len, 0, 0)
+}
+
+pub fn sys_mprotect(addr: usize, len: usize, prot: usize) -> SysResult {
+    sys_call(SyscallId::Mprotect, addr, len, prot, 0)
+}
+
+pub fn sys_fstat(fd: usize, stat: &mut Stat) -> SysResult {
+    sys_call(SyscallId::Fstat, fd, stat as *mut Stat as usize, 0, 0)
+}
+
+pub fn sys_fstatat(dirfd: isize, path: *const u8, stat: &mut Stat) -> SysResult {
+    sys_call(
+        SyscallId::Newfstatat,
+        dirfd as usize,
+        path as usize,
+        stat as *mut Stat as usize,
+        0,
+    )
+}
+
+pub fn sys_getdents64(fd: usize, buf: &mut [u8]) -> SysResult {
+    sys_call(
+        SyscallId::Getdents64,
+        fd,
+        buf.as_mut_ptr() as usize,
+        buf.len(),
+        0,
+    )
+}
+
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> SysResult {
+    sys_call(SyscallId::Lseek, fd, offset as usize, whence, 0)
+}
+
+pub fn sys_pread(fd: usize, base: *mut u8, len: usize, offset: usize) -> SysResult {
+    sys_call(SyscallId::Pread64, fd, base as usize, len, offset)
+}
+
+pub fn sys_pwrite(fd: usize, base: *const u8, len: usize, offset: usize) -> SysResult {
+    sys_call(SyscallId::Pwrite64, fd, base as usize, len, offset)
+}
+
+pub fn sys_getcwd(buf: &mut [u8]) -> SysResult {
+    sys_call(
+        SyscallId::Getcwd,
+        buf.as_mut_ptr() as usize,
+        buf.len(),
+        0,
+        0,
+    )
+}
+
+pub fn sys_chdir(path: *const u8) -> SysResult {
+    sys_call(SyscallId::Chdir, path as usize, 0, 0, 0)
+}
+
+pub fn sys_fchdir(fd: usize) -> SysResult {
+    sys_call(SyscallId::Fchdir, fd, 0, 0, 0)
+}
+
+pub fn sys_pipe(fds: &mut [i32; 2]) -> SysResult {
+    sys_call(SyscallId::Pipe2, fds.as_mut_ptr() as usize, 0, 0, 0)
+}
+
+pub fn sys_dup(fd: usize) -> SysResult {
+    sys_call(SyscallId::Dup, fd, 0, 0, 0)
+}
+
+pub fn sys_dup3(old_fd: usize, new_fd: usize, flags: i32) -> SysResult {
+    sys_call(SyscallId::Dup3, old_fd, new_fd, flags as usize, 0)
+}
+
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> SysResult {
+    sys_call(SyscallId::Fcntl, fd, cmd, arg, 0)
+}
+
+pub fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> SysResult {
+    sys_call(SyscallId::Ioctl, fd, cmd, arg, 0)
 }